@@ -1,4 +1,5 @@
 use std::env;
+use std::fs;
 use std::path::PathBuf;
 
 #[allow(dead_code)]
@@ -7,24 +8,55 @@ fn env_var_rerun(name: &str) -> Result<String, env::VarError> {
     env::var(name)
 }
 
-#[cfg(feature = "static")]
+#[cfg(feature = "vendored")]
 pub fn get_openssl_root_dir() -> PathBuf {
     let artifacts = openssl_src::Build::new().build();
     artifacts.lib_dir().parent().unwrap().to_path_buf()
 }
 
+/// Reads the version of libdatachannel from its CMake project.
+fn libdatachannel_version() -> String {
+    println!("cargo:rerun-if-changed=libdatachannel/CMakeLists.txt");
+    let cmake_lists = fs::read_to_string("libdatachannel/CMakeLists.txt")
+        .expect("Couldn't read libdatachannel/CMakeLists.txt, is the submodule checked out?");
+    cmake_lists
+        .split("project(libdatachannel")
+        .nth(1)
+        .and_then(|project| {
+            let mut tokens = project.split_whitespace();
+            tokens.find(|token| *token == "VERSION")?;
+            tokens.next()
+        })
+        .map(|version| version.trim_end_matches(')').to_string())
+        .expect("Couldn't find the libdatachannel version in its CMakeLists.txt")
+}
+
+/// Options of the libdatachannel build, following the features of this crate.
+fn configure(config: &mut cmake::Config) {
+    config.define("NO_WEBSOCKET", "ON");
+    config.define("NO_EXAMPLES", "ON");
+
+    if cfg!(not(feature = "media")) {
+        config.define("NO_MEDIA", "ON");
+    }
+    if cfg!(feature = "gnutls") {
+        config.define("USE_GNUTLS", "ON");
+    }
+}
+
 fn main() {
     let out_dir = env::var("OUT_DIR").unwrap();
+    println!(
+        "cargo:rustc-env=LIBDATACHANNEL_VERSION={}",
+        libdatachannel_version()
+    );
 
-    #[cfg(feature = "static")]
+    #[cfg(feature = "vendored")]
     {
         let mut config = cmake::Config::new("libdatachannel");
         config.build_target("datachannel-static");
         config.out_dir(&out_dir);
-
-        config.define("NO_WEBSOCKET", "ON");
-        config.define("NO_EXAMPLES", "ON");
-        config.define("NO_MEDIA", "ON");
+        configure(&mut config);
 
         config.define("OPENSSL_ROOT_DIR", get_openssl_root_dir());
         config.define("OPENSSL_USE_STATIC_LIBS", "TRUE");
@@ -34,10 +66,9 @@ fn main() {
 
     let mut config = cmake::Config::new("libdatachannel");
     config.out_dir(&out_dir);
-    config.define("NO_WEBSOCKET", "ON");
-    config.define("NO_EXAMPLES", "ON");
+    configure(&mut config);
 
-    #[cfg(not(feature = "static"))]
+    #[cfg(not(feature = "vendored"))]
     {
         if let Ok(openssl_root_dir) = env_var_rerun("OPENSSL_ROOT_DIR") {
             config.define("OPENSSL_ROOT_DIR", openssl_root_dir);
@@ -50,9 +81,9 @@ fn main() {
 
     config.build();
 
-    if cfg!(feature = "static") {
+    if cfg!(feature = "vendored") {
         // Link static libc++
-        #[cfg(feature = "static")]
+        #[cfg(feature = "vendored")]
         cpp_build::Config::new()
             .include(format!("{}/lib", out_dir))
             .build("src/lib.rs");
@@ -75,6 +106,15 @@ fn main() {
         );
         println!("cargo:rustc-link-lib=static=usrsctp");
 
+        // Link static libsrtp
+        if cfg!(feature = "media") {
+            println!(
+                "cargo:rustc-link-search=native={}/build/deps/libsrtp",
+                out_dir
+            );
+            println!("cargo:rustc-link-lib=static=srtp2");
+        }
+
         // Link static libdatachannel
         println!("cargo:rustc-link-search=native={}/build", out_dir);
         println!("cargo:rustc-link-lib=static=datachannel-static");
@@ -2,4 +2,15 @@
 #![allow(non_camel_case_types)]
 #![allow(non_snake_case)]
 
+#[cfg(all(feature = "openssl", feature = "gnutls"))]
+compile_error!(
+    "The openssl and gnutls features are exclusive, disable default features to use gnutls \
+     (vendored builds use OpenSSL)"
+);
+#[cfg(not(any(feature = "openssl", feature = "gnutls")))]
+compile_error!("A crypto backend is needed, enable either the openssl or the gnutls feature");
+
 include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
+
+/// The version declared by the bundled libdatachannel sources, read at build time.
+pub const LIBDATACHANNEL_VERSION: &str = env!("LIBDATACHANNEL_VERSION");
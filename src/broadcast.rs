@@ -0,0 +1,147 @@
+use std::sync::Arc;
+
+use async_channel::{Receiver, Sender};
+use parking_lot::Mutex;
+
+use crate::datachannel::RtcDataChannel;
+use crate::diff::SdpDiff;
+use crate::peerconnection::{
+    CandidatePair, ConnectionState, FailureReason, GatheringState, IceCandidate,
+    PeerConnectionHandler, SessionDescription, SignalingState,
+};
+
+/// A peer connection event, as published by [`BroadcastHandler`].
+#[derive(Debug, Clone)]
+pub enum ConnectionEvent {
+    Description(SessionDescription),
+    DescriptionDiff(SdpDiff),
+    Candidate(IceCandidate),
+    ConnectionStateChange(ConnectionState),
+    GatheringStateChange(GatheringState),
+    SignalingStateChange(SignalingState),
+    CandidatePairChange {
+        old: Option<CandidatePair>,
+        new: CandidatePair,
+    },
+    Failure(FailureReason),
+}
+
+/// A cloneable handle used to subscribe to the events of a [`BroadcastHandler`].
+#[derive(Clone, Default)]
+pub struct EventBroadcaster {
+    subscribers: Arc<Mutex<Vec<Sender<ConnectionEvent>>>>,
+}
+
+impl EventBroadcaster {
+    /// Returns a new receiver of all the events published from now on.
+    ///
+    /// Each subscriber gets its own unbounded queue, dropping the receiver unsubscribes.
+    pub fn subscribe(&self) -> Receiver<ConnectionEvent> {
+        let (tx, rx) = async_channel::unbounded();
+        self.subscribers.lock().push(tx);
+        rx
+    }
+
+    fn publish(&self, event: ConnectionEvent) {
+        self.subscribers
+            .lock()
+            .retain(|tx| tx.try_send(event.clone()).is_ok());
+    }
+}
+
+/// A [`PeerConnectionHandler`] wrapper that publishes connection events to any number of
+/// subscribers, in addition to forwarding them to the wrapped handler.
+pub struct BroadcastHandler<P> {
+    inner: P,
+    broadcaster: EventBroadcaster,
+}
+
+impl<P> BroadcastHandler<P> {
+    pub fn new(inner: P) -> Self {
+        BroadcastHandler {
+            inner,
+            broadcaster: EventBroadcaster::default(),
+        }
+    }
+
+    /// Returns a handle that can be kept to subscribe once the handler has been moved into
+    /// a [`RtcPeerConnection`].
+    ///
+    /// [`RtcPeerConnection`]: crate::RtcPeerConnection
+    pub fn broadcaster(&self) -> EventBroadcaster {
+        self.broadcaster.clone()
+    }
+
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    pub fn inner_mut(&mut self) -> &mut P {
+        &mut self.inner
+    }
+}
+
+impl<P> PeerConnectionHandler for BroadcastHandler<P>
+where
+    P: PeerConnectionHandler,
+{
+    type DCH = P::DCH;
+
+    fn data_channel_handler(&mut self) -> Self::DCH {
+        self.inner.data_channel_handler()
+    }
+
+    fn on_description(&mut self, sess_desc: SessionDescription) {
+        self.broadcaster
+            .publish(ConnectionEvent::Description(sess_desc.clone()));
+        self.inner.on_description(sess_desc)
+    }
+
+    fn on_description_diff(&mut self, diff: SdpDiff) {
+        self.broadcaster
+            .publish(ConnectionEvent::DescriptionDiff(diff.clone()));
+        self.inner.on_description_diff(diff)
+    }
+
+    fn on_candidate(&mut self, cand: IceCandidate) {
+        self.broadcaster
+            .publish(ConnectionEvent::Candidate(cand.clone()));
+        self.inner.on_candidate(cand)
+    }
+
+    fn on_connection_state_change(&mut self, state: ConnectionState) {
+        self.broadcaster
+            .publish(ConnectionEvent::ConnectionStateChange(state));
+        self.inner.on_connection_state_change(state)
+    }
+
+    fn on_gathering_state_change(&mut self, state: GatheringState) {
+        self.broadcaster
+            .publish(ConnectionEvent::GatheringStateChange(state));
+        self.inner.on_gathering_state_change(state)
+    }
+
+    fn on_signaling_state_change(&mut self, state: SignalingState) {
+        self.broadcaster
+            .publish(ConnectionEvent::SignalingStateChange(state));
+        self.inner.on_signaling_state_change(state)
+    }
+
+    fn on_candidate_pair_change(&mut self, old: Option<CandidatePair>, new: CandidatePair) {
+        self.broadcaster
+            .publish(ConnectionEvent::CandidatePairChange {
+                old: old.clone(),
+                new: new.clone(),
+            });
+        self.inner.on_candidate_pair_change(old, new)
+    }
+
+    fn on_failure(&mut self, reason: FailureReason) {
+        self.broadcaster.publish(ConnectionEvent::Failure(reason));
+        self.inner.on_failure(reason)
+    }
+
+    fn on_data_channel(&mut self, data_channel: Box<RtcDataChannel<Self::DCH>>) {
+        self.inner.on_data_channel(data_channel)
+    }
+}
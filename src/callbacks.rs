@@ -0,0 +1,399 @@
+use crate::datachannel::{DataChannelHandler, Message, RtcDataChannel, Subscriptions};
+use crate::diff::SdpDiff;
+use crate::peerconnection::{
+    CandidatePair, ConnectionState, FailureReason, GatheringState, IceCandidate,
+    PeerConnectionHandler, RtcPeerConnection, SessionDescription, SignalingState,
+};
+use crate::quota::QuotaScope;
+
+type Callback = Box<dyn FnMut() + Send>;
+type ArgCallback<T> = Box<dyn FnMut(T) + Send>;
+type ErrorCallback = Box<dyn FnMut(&str) + Send>;
+type MessageCallback = Box<dyn FnMut(&[u8]) + Send>;
+type TypedMessageCallback = Box<dyn FnMut(Message) + Send>;
+type OwnedMessageCallback = Box<dyn FnMut(Vec<u8>) + Send>;
+type CandidatePairCallback = Box<dyn FnMut(Option<CandidatePair>, CandidatePair) + Send>;
+type DataChannelFactory = Box<dyn FnMut() -> DataChannelCallbacks + Send>;
+
+/// A [`DataChannelHandler`] made of closures, an alternative to implementing the trait.
+///
+/// Events without a registered closure are ignored, and not even dispatched by
+/// libdatachannel for the optional ones (see [`Subscriptions`]).
+///
+/// ```ignore
+/// let dc = pc.create_data_channel(
+///     "chat",
+///     DataChannelCallbacks::default()
+///         .on_open(|| log::info!("Open"))
+///         .on_message(|msg| log::info!("Received {} bytes", msg.len())),
+/// )?;
+/// ```
+#[derive(Default)]
+pub struct DataChannelCallbacks {
+    open: Option<Callback>,
+    closed: Option<Callback>,
+    error: Option<ErrorCallback>,
+    message: Option<MessageCallback>,
+    typed_message: Option<TypedMessageCallback>,
+    message_owned: Option<OwnedMessageCallback>,
+    buffered_amount_low: Option<Callback>,
+    available: Option<Callback>,
+    quota_exceeded: Option<ArgCallback<QuotaScope>>,
+}
+
+impl DataChannelCallbacks {
+    pub fn on_open<F: FnMut() + Send + 'static>(mut self, f: F) -> Self {
+        self.open = Some(Box::new(f));
+        self
+    }
+
+    pub fn on_closed<F: FnMut() + Send + 'static>(mut self, f: F) -> Self {
+        self.closed = Some(Box::new(f));
+        self
+    }
+
+    pub fn on_error<F: FnMut(&str) + Send + 'static>(mut self, f: F) -> Self {
+        self.error = Some(Box::new(f));
+        self
+    }
+
+    pub fn on_message<F: FnMut(&[u8]) + Send + 'static>(mut self, f: F) -> Self {
+        self.message = Some(Box::new(f));
+        self
+    }
+
+    /// Receives messages along with their kind, replaces the closure set with
+    /// `on_message`.
+    pub fn on_typed_message<F: FnMut(Message) + Send + 'static>(mut self, f: F) -> Self {
+        self.typed_message = Some(Box::new(f));
+        self
+    }
+
+    /// Receives messages as owned buffers, replaces the closure set with `on_message`.
+    pub fn on_message_owned<F: FnMut(Vec<u8>) + Send + 'static>(mut self, f: F) -> Self {
+        self.message_owned = Some(Box::new(f));
+        self
+    }
+
+    pub fn on_buffered_amount_low<F: FnMut() + Send + 'static>(mut self, f: F) -> Self {
+        self.buffered_amount_low = Some(Box::new(f));
+        self
+    }
+
+    pub fn on_available<F: FnMut() + Send + 'static>(mut self, f: F) -> Self {
+        self.available = Some(Box::new(f));
+        self
+    }
+
+    pub fn on_quota_exceeded<F: FnMut(QuotaScope) + Send + 'static>(mut self, f: F) -> Self {
+        self.quota_exceeded = Some(Box::new(f));
+        self
+    }
+}
+
+impl DataChannelHandler for DataChannelCallbacks {
+    fn on_open(&mut self) {
+        if let Some(f) = &mut self.open {
+            f()
+        }
+    }
+
+    fn on_closed(&mut self) {
+        if let Some(f) = &mut self.closed {
+            f()
+        }
+    }
+
+    fn on_error(&mut self, err: &str) {
+        if let Some(f) = &mut self.error {
+            f(err)
+        }
+    }
+
+    fn on_message(&mut self, msg: &[u8]) {
+        if let Some(f) = &mut self.message {
+            f(msg)
+        }
+    }
+
+    fn on_typed_message(&mut self, msg: Message) {
+        match &mut self.typed_message {
+            Some(f) => f(msg),
+            None => self.on_message(msg.as_bytes()),
+        }
+    }
+
+    fn on_buffered_amount_low(&mut self) {
+        if let Some(f) = &mut self.buffered_amount_low {
+            f()
+        }
+    }
+
+    fn on_available(&mut self) {
+        if let Some(f) = &mut self.available {
+            f()
+        }
+    }
+
+    fn on_quota_exceeded(&mut self, scope: QuotaScope) {
+        if let Some(f) = &mut self.quota_exceeded {
+            f(scope)
+        }
+    }
+
+    fn owned_messages(&self) -> bool {
+        self.message_owned.is_some()
+    }
+
+    fn on_message_owned(&mut self, msg: Vec<u8>) {
+        if let Some(f) = &mut self.message_owned {
+            f(msg)
+        }
+    }
+
+    fn subscriptions(&self) -> Subscriptions {
+        Subscriptions {
+            error: self.error.is_some(),
+            // Quotas are enforced on received messages
+            message: self.message.is_some()
+                || self.typed_message.is_some()
+                || self.message_owned.is_some()
+                || self.quota_exceeded.is_some(),
+            buffered_amount_low: self.buffered_amount_low.is_some(),
+            available: self.available.is_some(),
+        }
+    }
+}
+
+/// A [`PeerConnectionHandler`] made of closures, an alternative to implementing the trait.
+///
+/// Incoming data channels get a default [`DataChannelCallbacks`] unless a factory is set
+/// with [`data_channel_handler`]. Closures can also be registered once the connection is
+/// created, through the `on_*` methods of `RtcPeerConnection<PeerConnectionCallbacks>`.
+///
+/// [`data_channel_handler`]: PeerConnectionCallbacks::data_channel_handler
+#[derive(Default)]
+pub struct PeerConnectionCallbacks {
+    dc_handler: Option<DataChannelFactory>,
+    description: Option<ArgCallback<SessionDescription>>,
+    description_diff: Option<ArgCallback<SdpDiff>>,
+    candidate: Option<ArgCallback<IceCandidate>>,
+    connection_state_change: Option<ArgCallback<ConnectionState>>,
+    gathering_state_change: Option<ArgCallback<GatheringState>>,
+    signaling_state_change: Option<ArgCallback<SignalingState>>,
+    candidate_pair_change: Option<CandidatePairCallback>,
+    failure: Option<ArgCallback<FailureReason>>,
+    data_channel: Option<ArgCallback<Box<RtcDataChannel<DataChannelCallbacks>>>>,
+}
+
+impl PeerConnectionCallbacks {
+    pub fn data_channel_handler<F>(mut self, f: F) -> Self
+    where
+        F: FnMut() -> DataChannelCallbacks + Send + 'static,
+    {
+        self.dc_handler = Some(Box::new(f));
+        self
+    }
+
+    pub fn on_description<F>(mut self, f: F) -> Self
+    where
+        F: FnMut(SessionDescription) + Send + 'static,
+    {
+        self.description = Some(Box::new(f));
+        self
+    }
+
+    pub fn on_description_diff<F>(mut self, f: F) -> Self
+    where
+        F: FnMut(SdpDiff) + Send + 'static,
+    {
+        self.description_diff = Some(Box::new(f));
+        self
+    }
+
+    pub fn on_candidate<F>(mut self, f: F) -> Self
+    where
+        F: FnMut(IceCandidate) + Send + 'static,
+    {
+        self.candidate = Some(Box::new(f));
+        self
+    }
+
+    pub fn on_connection_state_change<F>(mut self, f: F) -> Self
+    where
+        F: FnMut(ConnectionState) + Send + 'static,
+    {
+        self.connection_state_change = Some(Box::new(f));
+        self
+    }
+
+    pub fn on_gathering_state_change<F>(mut self, f: F) -> Self
+    where
+        F: FnMut(GatheringState) + Send + 'static,
+    {
+        self.gathering_state_change = Some(Box::new(f));
+        self
+    }
+
+    pub fn on_signaling_state_change<F>(mut self, f: F) -> Self
+    where
+        F: FnMut(SignalingState) + Send + 'static,
+    {
+        self.signaling_state_change = Some(Box::new(f));
+        self
+    }
+
+    pub fn on_candidate_pair_change<F>(mut self, f: F) -> Self
+    where
+        F: FnMut(Option<CandidatePair>, CandidatePair) + Send + 'static,
+    {
+        self.candidate_pair_change = Some(Box::new(f));
+        self
+    }
+
+    pub fn on_failure<F>(mut self, f: F) -> Self
+    where
+        F: FnMut(FailureReason) + Send + 'static,
+    {
+        self.failure = Some(Box::new(f));
+        self
+    }
+
+    pub fn on_data_channel<F>(mut self, f: F) -> Self
+    where
+        F: FnMut(Box<RtcDataChannel<DataChannelCallbacks>>) + Send + 'static,
+    {
+        self.data_channel = Some(Box::new(f));
+        self
+    }
+}
+
+impl PeerConnectionHandler for PeerConnectionCallbacks {
+    type DCH = DataChannelCallbacks;
+
+    fn data_channel_handler(&mut self) -> Self::DCH {
+        match &mut self.dc_handler {
+            Some(f) => f(),
+            None => DataChannelCallbacks::default(),
+        }
+    }
+
+    fn on_description(&mut self, sess_desc: SessionDescription) {
+        if let Some(f) = &mut self.description {
+            f(sess_desc)
+        }
+    }
+
+    fn on_description_diff(&mut self, diff: SdpDiff) {
+        if let Some(f) = &mut self.description_diff {
+            f(diff)
+        }
+    }
+
+    fn on_candidate(&mut self, cand: IceCandidate) {
+        if let Some(f) = &mut self.candidate {
+            f(cand)
+        }
+    }
+
+    fn on_connection_state_change(&mut self, state: ConnectionState) {
+        if let Some(f) = &mut self.connection_state_change {
+            f(state)
+        }
+    }
+
+    fn on_gathering_state_change(&mut self, state: GatheringState) {
+        if let Some(f) = &mut self.gathering_state_change {
+            f(state)
+        }
+    }
+
+    fn on_signaling_state_change(&mut self, state: SignalingState) {
+        if let Some(f) = &mut self.signaling_state_change {
+            f(state)
+        }
+    }
+
+    fn on_candidate_pair_change(&mut self, old: Option<CandidatePair>, new: CandidatePair) {
+        if let Some(f) = &mut self.candidate_pair_change {
+            f(old, new)
+        }
+    }
+
+    fn on_failure(&mut self, reason: FailureReason) {
+        if let Some(f) = &mut self.failure {
+            f(reason)
+        }
+    }
+
+    fn on_data_channel(&mut self, data_channel: Box<RtcDataChannel<Self::DCH>>) {
+        if let Some(f) = &mut self.data_channel {
+            f(data_channel)
+        }
+    }
+}
+
+impl RtcPeerConnection<PeerConnectionCallbacks> {
+    pub fn on_description<F>(&mut self, f: F)
+    where
+        F: FnMut(SessionDescription) + Send + 'static,
+    {
+        self.with_handler(|h| h.description = Some(Box::new(f)))
+    }
+
+    pub fn on_description_diff<F>(&mut self, f: F)
+    where
+        F: FnMut(SdpDiff) + Send + 'static,
+    {
+        self.with_handler(|h| h.description_diff = Some(Box::new(f)))
+    }
+
+    pub fn on_candidate<F>(&mut self, f: F)
+    where
+        F: FnMut(IceCandidate) + Send + 'static,
+    {
+        self.with_handler(|h| h.candidate = Some(Box::new(f)))
+    }
+
+    pub fn on_connection_state_change<F>(&mut self, f: F)
+    where
+        F: FnMut(ConnectionState) + Send + 'static,
+    {
+        self.with_handler(|h| h.connection_state_change = Some(Box::new(f)))
+    }
+
+    pub fn on_gathering_state_change<F>(&mut self, f: F)
+    where
+        F: FnMut(GatheringState) + Send + 'static,
+    {
+        self.with_handler(|h| h.gathering_state_change = Some(Box::new(f)))
+    }
+
+    pub fn on_signaling_state_change<F>(&mut self, f: F)
+    where
+        F: FnMut(SignalingState) + Send + 'static,
+    {
+        self.with_handler(|h| h.signaling_state_change = Some(Box::new(f)))
+    }
+
+    pub fn on_candidate_pair_change<F>(&mut self, f: F)
+    where
+        F: FnMut(Option<CandidatePair>, CandidatePair) + Send + 'static,
+    {
+        self.with_handler(|h| h.candidate_pair_change = Some(Box::new(f)))
+    }
+
+    pub fn on_failure<F>(&mut self, f: F)
+    where
+        F: FnMut(FailureReason) + Send + 'static,
+    {
+        self.with_handler(|h| h.failure = Some(Box::new(f)))
+    }
+
+    pub fn on_data_channel<F>(&mut self, f: F)
+    where
+        F: FnMut(Box<RtcDataChannel<DataChannelCallbacks>>) + Send + 'static,
+    {
+        self.with_handler(|h| h.data_channel = Some(Box::new(f)))
+    }
+}
@@ -0,0 +1,52 @@
+use datachannel_sys as sys;
+use serde::Serialize;
+
+use crate::track::media_supported;
+
+/// The version declared by the libdatachannel sources bundled with `datachannel-sys`,
+/// e.g. `0.13.1`, as read from their CMake project when building.
+///
+/// libdatachannel is always built from these sources, but this isn't queried from the
+/// library, which has no API for it. Without the `vendored` feature it is linked
+/// dynamically, and the library loaded at runtime may be another version.
+pub fn version() -> &'static str {
+    sys::LIBDATACHANNEL_VERSION
+}
+
+/// The TLS library used by libdatachannel for DTLS.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[non_exhaustive]
+pub enum CryptoBackend {
+    OpenSsl,
+    GnuTls,
+}
+
+/// The optional functionalities available in this build, see [`capabilities`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[non_exhaustive]
+pub struct Capabilities {
+    pub version: &'static str,
+    /// Media tracks, see [`media_supported`].
+    pub media: bool,
+    /// WebSocket signaling, see `WebSocketSignaling`.
+    pub websocket: bool,
+    pub crypto: CryptoBackend,
+    /// Whether libdatachannel and its dependencies are statically linked.
+    pub static_build: bool,
+}
+
+/// Reports the optional functionalities available in this build, so that applications
+/// can check for them at runtime.
+pub fn capabilities() -> Capabilities {
+    Capabilities {
+        version: version(),
+        media: media_supported(),
+        websocket: cfg!(feature = "websocket"),
+        crypto: if cfg!(feature = "gnutls") {
+            CryptoBackend::GnuTls
+        } else {
+            CryptoBackend::OpenSsl
+        },
+        static_build: cfg!(feature = "vendored"),
+    }
+}
@@ -0,0 +1,145 @@
+use std::time::{Duration, Instant};
+
+/// Bounds of a [`ChunkSizer`].
+#[derive(Debug, Clone)]
+pub struct ChunkConfig {
+    pub min_size: usize,
+    pub max_size: usize,
+    /// Size used until the drain rate and RTT are known.
+    pub initial_size: usize,
+    /// RTT assumed until one is recorded.
+    pub default_rtt: Duration,
+}
+
+impl Default for ChunkConfig {
+    fn default() -> Self {
+        ChunkConfig {
+            min_size: 4 * 1024,
+            max_size: 256 * 1024,
+            initial_size: 16 * 1024,
+            default_rtt: Duration::from_millis(100),
+        }
+    }
+}
+
+impl ChunkConfig {
+    pub fn min_size(mut self, min_size: usize) -> Self {
+        self.min_size = min_size;
+        self
+    }
+
+    pub fn max_size(mut self, max_size: usize) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    pub fn initial_size(mut self, initial_size: usize) -> Self {
+        self.initial_size = initial_size;
+        self
+    }
+
+    pub fn default_rtt(mut self, default_rtt: Duration) -> Self {
+        self.default_rtt = default_rtt;
+        self
+    }
+}
+
+/// Weight of new samples in the moving averages.
+const SMOOTHING: f64 = 0.25;
+
+/// Picks the chunk size of bulk transfers from the measured drain rate of the send
+/// buffer and the RTT, so that transfers use large chunks on fast links and stay
+/// responsive on slow or distant ones.
+///
+/// Senders report the bytes they send with [`record_sent`] and periodically sample
+/// the buffered amount of the channel with [`record_buffered`] (e.g. on each
+/// `on_buffered_amount_low`), then split their data with [`split`]. The chunk size
+/// targets a quarter of the bandwidth-delay product.
+///
+/// [`record_sent`]: ChunkSizer::record_sent
+/// [`record_buffered`]: ChunkSizer::record_buffered
+/// [`split`]: ChunkSizer::split
+#[derive(Debug, Clone)]
+pub struct ChunkSizer {
+    config: ChunkConfig,
+    rtt: Option<Duration>,
+    /// Bytes per second.
+    drain_rate: Option<f64>,
+    sent: usize,
+    last_sample: Option<(Instant, usize)>,
+}
+
+impl ChunkSizer {
+    pub fn new(config: ChunkConfig) -> Self {
+        ChunkSizer {
+            config,
+            rtt: None,
+            drain_rate: None,
+            sent: 0,
+            last_sample: None,
+        }
+    }
+
+    pub fn record_rtt(&mut self, rtt: Duration) {
+        self.rtt = Some(match self.rtt {
+            Some(prev) => prev.mul_f64(1.0 - SMOOTHING) + rtt.mul_f64(SMOOTHING),
+            None => rtt,
+        });
+    }
+
+    pub fn record_sent(&mut self, size: usize) {
+        self.sent += size;
+    }
+
+    pub fn record_buffered(&mut self, buffered: usize) {
+        self.record_buffered_at(buffered, Instant::now())
+    }
+
+    /// Records the buffered amount of the channel, sampled at `now`.
+    pub fn record_buffered_at(&mut self, buffered: usize, now: Instant) {
+        if let Some((at, prev_buffered)) = self.last_sample {
+            let elapsed = now.duration_since(at).as_secs_f64();
+            if elapsed > 0.0 {
+                let drained = (prev_buffered + self.sent).saturating_sub(buffered);
+                let rate = drained as f64 / elapsed;
+                self.drain_rate = Some(match self.drain_rate {
+                    Some(prev) => prev * (1.0 - SMOOTHING) + rate * SMOOTHING,
+                    None => rate,
+                });
+            }
+        }
+        self.sent = 0;
+        self.last_sample = Some((now, buffered));
+    }
+
+    pub fn rtt(&self) -> Option<Duration> {
+        self.rtt
+    }
+
+    /// The measured drain rate, in bytes per second.
+    pub fn drain_rate(&self) -> Option<f64> {
+        self.drain_rate
+    }
+
+    pub fn chunk_size(&self) -> usize {
+        let size = match self.drain_rate {
+            Some(rate) => {
+                let rtt = self.rtt.unwrap_or(self.config.default_rtt);
+                (rate * rtt.as_secs_f64() / 4.0) as usize
+            }
+            None => self.config.initial_size,
+        };
+        size.max(self.config.min_size).min(self.config.max_size)
+    }
+
+    /// Splits the next chunk off `data`, returning it along with the remaining data.
+    pub fn split<'a>(&self, data: &'a [u8]) -> (&'a [u8], &'a [u8]) {
+        data.split_at(self.chunk_size().min(data.len()))
+    }
+}
+
+impl Default for ChunkSizer {
+    fn default() -> Self {
+        Self::new(ChunkConfig::default())
+    }
+}
@@ -1,8 +1,16 @@
 use std::ffi::CString;
+use std::net::IpAddr;
 use std::os::raw::c_char;
+use std::sync::Arc;
+use std::time::Duration;
 
 use datachannel_sys as sys;
 use derivative::Derivative;
+use parking_lot::RwLock;
+
+use crate::peerconnection::IceCandidate;
+
+pub(crate) type CandidateFilter = Arc<dyn Fn(&IceCandidate) -> bool + Send + Sync>;
 
 #[derive(Derivative)]
 #[derivative(Debug)]
@@ -16,7 +24,21 @@ pub struct RtcConfig {
     pub port_range_end: u16,
     pub mtu: i32,
     pub max_message_size: i32,
+    /// Leaves the initial negotiation to the application, see
+    /// `RtcPeerConnection::renegotiate` for the later ones.
     pub disable_auto_negotiation: bool,
+    /// Handled by this crate, see [`RtcConfig::disable_trickle`].
+    pub disable_trickle: bool,
+    /// Handled by this crate, see [`RtcConfig::connect_timeout`].
+    pub connect_timeout: Option<Duration>,
+    /// Handled by this crate, see [`RtcConfig::disconnect_timeout`].
+    pub disconnect_timeout: Option<Duration>,
+    /// Handled by this crate, see [`RtcConfig::candidate_pair_interval`].
+    pub candidate_pair_interval: Option<Duration>,
+    /// Handled by this crate, see [`RtcConfig::address_family`].
+    pub address_family: AddressFamily,
+    #[derivative(Debug = "ignore")]
+    pub(crate) candidate_filter: Option<CandidateFilter>,
 }
 
 unsafe impl Send for RtcConfig {}
@@ -40,9 +62,45 @@ impl RtcConfig {
             mtu: 0,
             max_message_size: 0,
             disable_auto_negotiation: false,
+            disable_trickle: false,
+            connect_timeout: None,
+            disconnect_timeout: None,
+            candidate_pair_interval: None,
+            address_family: AddressFamily::DualStack,
+            candidate_filter: None,
         }
     }
 
+    /// Replaces the ICE servers, e.g. when rolling out a new TURN fleet.
+    pub fn ice_servers<S: AsRef<str>>(self, ice_servers: &[S]) -> Self {
+        RtcConfig::new(ice_servers).copy_settings(&self)
+    }
+
+    /// Copies all the settings of `other` but the ICE servers.
+    fn copy_settings(mut self, other: &RtcConfig) -> Self {
+        self.certificate_type = other.certificate_type;
+        self.enable_ice_tcp = other.enable_ice_tcp;
+        self.port_range_begin = other.port_range_begin;
+        self.port_range_end = other.port_range_end;
+        self.mtu = other.mtu;
+        self.max_message_size = other.max_message_size;
+        self.disable_auto_negotiation = other.disable_auto_negotiation;
+        self.disable_trickle = other.disable_trickle;
+        self.connect_timeout = other.connect_timeout;
+        self.disconnect_timeout = other.disconnect_timeout;
+        self.candidate_pair_interval = other.candidate_pair_interval;
+        self.address_family = other.address_family;
+        self.candidate_filter = other.candidate_filter.clone();
+        self
+    }
+
+    /// Selects the type of the generated DTLS certificate, for peers that only accept
+    /// one of them (e.g. RSA).
+    pub fn certificate_type(mut self, certificate_type: CertificateType) -> Self {
+        self.certificate_type = certificate_type;
+        self
+    }
+
     pub fn enable_ice_tcp(mut self) -> Self {
         self.enable_ice_tcp = true;
         self
@@ -58,6 +116,150 @@ impl RtcConfig {
         self
     }
 
+    /// Sets the MTU used for SCTP packets, 0 means automatic.
+    pub fn mtu(mut self, mtu: i32) -> Self {
+        self.mtu = mtu;
+        self
+    }
+
+    /// Sets the maximum message size advertised to the remote peer, 0 means default.
+    pub fn max_message_size(mut self, max_message_size: i32) -> Self {
+        self.max_message_size = max_message_size;
+        self
+    }
+
+    /// Disables trickle ICE, for signaling protocols that exchange a single offer and
+    /// answer: `on_candidate` is never called, and `on_description` is delayed until
+    /// gathering completes to carry all the local candidates.
+    pub fn disable_trickle(mut self) -> Self {
+        self.disable_trickle = true;
+        self
+    }
+
+    /// Fails connections that don't get connected within `timeout` of their creation.
+    ///
+    /// The underlying connection is then closed, and the handler notified of
+    /// [`ConnectionState::Failed`] with [`FailureReason::ConnectTimeout`] as
+    /// `failure_reason`.
+    ///
+    /// [`ConnectionState::Failed`]: crate::ConnectionState::Failed
+    /// [`FailureReason::ConnectTimeout`]: crate::FailureReason::ConnectTimeout
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Detects connected peers that stopped responding (e.g. pulled cable, killed
+    /// process) from the data received on the data channels of the connection, much
+    /// faster than ICE consent freshness.
+    ///
+    /// Without incoming data for half of `timeout`, the handler is notified of
+    /// [`ConnectionState::Disconnected`] (and of [`ConnectionState::Connected`] if data
+    /// comes back). After `timeout`, the connection is closed and the handler notified of
+    /// [`ConnectionState::Failed`] with [`FailureReason::PeerUnresponsive`] as
+    /// `failure_reason`. Both happen within a tenth of `timeout` of their deadline.
+    ///
+    /// Off by default, and only suited to applications sending their own heartbeats:
+    /// nothing is sent by this crate, and media tracks aren't watched, so idle peers and
+    /// peers only sending media are failed as unresponsive.
+    ///
+    /// Messages count whether or not handlers subscribe to them: for channels without the
+    /// message subscription, a message counts when it becomes available and when it is
+    /// read with `RtcDataChannel::receive`.
+    ///
+    /// [`ConnectionState::Disconnected`]: crate::ConnectionState::Disconnected
+    /// [`ConnectionState::Connected`]: crate::ConnectionState::Connected
+    /// [`ConnectionState::Failed`]: crate::ConnectionState::Failed
+    /// [`FailureReason::PeerUnresponsive`]: crate::FailureReason::PeerUnresponsive
+    pub fn disconnect_timeout(mut self, timeout: Duration) -> Self {
+        self.disconnect_timeout = Some(timeout);
+        self
+    }
+
+    /// Checks the selected candidate pair every `interval` once connected, calling
+    /// [`PeerConnectionHandler::on_candidate_pair_change`] when it switched, e.g. after
+    /// a network change.
+    ///
+    /// libdatachannel doesn't signal such switches, without this option the pair is only
+    /// checked when the connection gets connected and by
+    /// `RtcPeerConnection::check_candidate_pair`.
+    ///
+    /// [`PeerConnectionHandler::on_candidate_pair_change`]: crate::PeerConnectionHandler::on_candidate_pair_change
+    pub fn candidate_pair_interval(mut self, interval: Duration) -> Self {
+        self.candidate_pair_interval = Some(interval);
+        self
+    }
+
+    /// Drops the local candidates for which `filter` returns false, before they are
+    /// passed to `on_candidate` or embedded in local descriptions. Successive filters add
+    /// up.
+    ///
+    /// Filtered candidates are still used by the ICE agent, which sends connectivity
+    /// checks from all of them.
+    pub fn candidate_filter<F>(mut self, filter: F) -> Self
+    where
+        F: Fn(&IceCandidate) -> bool + Send + Sync + 'static,
+    {
+        self.candidate_filter = Some(match self.candidate_filter.take() {
+            Some(previous) => Arc::new(move |cand| previous(cand) && filter(cand)),
+            None => Arc::new(filter),
+        });
+        self
+    }
+
+    /// Only signals relay candidates, which needs a TURN server in `ice_servers`.
+    pub fn relay_only(self) -> Self {
+        self.candidate_filter(|cand| cand.candidate_type() == Some("relay"))
+    }
+
+    /// Doesn't signal host candidates, which carry local addresses.
+    pub fn disable_host_candidates(self) -> Self {
+        self.candidate_filter(|cand| cand.candidate_type() != Some("host"))
+    }
+
+    /// Doesn't signal mDNS candidates, whose address is a `.local` host name.
+    pub fn disable_mdns(self) -> Self {
+        self.candidate_filter(|cand| match cand.address() {
+            Some(addr) => !addr.ends_with(".local"),
+            None => true,
+        })
+    }
+
+    /// Only signals the candidates gathered on the network interface of `addr`, so that
+    /// multi-homed hosts don't advertise the addresses of their other interfaces.
+    ///
+    /// Host candidates must have `addr` as address, and reflexive candidates as related
+    /// address. Relay candidates, and candidates whose address is hidden (mDNS host names,
+    /// `0.0.0.0` related addresses), are kept.
+    ///
+    /// This doesn't bind the connection to the interface: libdatachannel 0.13 has no bind
+    /// address setting, it still gathers candidates on all interfaces and checks
+    /// connectivity from them, so peer reflexive pairs may form on other interfaces.
+    pub fn signal_only_address(self, addr: IpAddr) -> Self {
+        self.candidate_filter(move |cand| {
+            let local = match cand.candidate_type() {
+                Some("host") => cand.address(),
+                Some("srflx") | Some("prflx") => cand.related_address(),
+                _ => None,
+            };
+            match local.and_then(|local| local.parse::<IpAddr>().ok()) {
+                Some(local) => local == addr || local.is_unspecified(),
+                None => true,
+            }
+        })
+    }
+
+    /// Restricts the candidates to an address family, e.g. to skip doomed IPv6 pairs on
+    /// networks with broken IPv6 connectivity.
+    ///
+    /// Local candidates of the other family aren't signaled, and remote ones (added or in
+    /// remote descriptions) are ignored, so that no connectivity check is spent on them.
+    /// Candidates with an mDNS host name as address are kept. The last call wins.
+    pub fn address_family(mut self, address_family: AddressFamily) -> Self {
+        self.address_family = address_family;
+        self
+    }
+
     pub(crate) fn as_raw(&self) -> sys::rtcConfiguration {
         sys::rtcConfiguration {
             iceServers: self.ice_servers_ptrs.as_ptr() as *mut *const c_char,
@@ -87,10 +289,51 @@ impl Clone for RtcConfig {
             mtu: self.mtu,
             max_message_size: self.max_message_size,
             disable_auto_negotiation: self.disable_auto_negotiation,
+            disable_trickle: self.disable_trickle,
+            connect_timeout: self.connect_timeout,
+            disconnect_timeout: self.disconnect_timeout,
+            candidate_pair_interval: self.candidate_pair_interval,
+            address_family: self.address_family,
+            candidate_filter: self.candidate_filter.clone(),
         }
     }
 }
 
+/// A [`RtcConfig`] that can be replaced at runtime, shared by the code creating
+/// connections.
+///
+/// Connections created after an update use the new configuration, existing ones keep
+/// theirs: libdatachannel doesn't support ICE restarts, which would be needed to apply
+/// new ICE servers to established connections.
+#[derive(Debug, Clone)]
+pub struct SharedConfig {
+    inner: Arc<RwLock<Arc<RtcConfig>>>,
+}
+
+impl SharedConfig {
+    pub fn new(config: RtcConfig) -> Self {
+        SharedConfig {
+            inner: Arc::new(RwLock::new(Arc::new(config))),
+        }
+    }
+
+    /// The current configuration, to be passed to `RtcPeerConnection::new`.
+    pub fn get(&self) -> Arc<RtcConfig> {
+        self.inner.read().clone()
+    }
+
+    pub fn set(&self, config: RtcConfig) {
+        *self.inner.write() = Arc::new(config);
+    }
+
+    /// Replaces the configuration with the one returned by `f`, e.g.
+    /// `shared.update(|config| config.ice_servers(&new_servers))`.
+    pub fn update(&self, f: impl FnOnce(RtcConfig) -> RtcConfig) {
+        let mut config = self.inner.write();
+        *config = Arc::new(f((**config).clone()));
+    }
+}
+
 #[derive(Debug, PartialEq, Clone, Copy)]
 #[repr(u32)]
 pub enum CertificateType {
@@ -98,3 +341,26 @@ pub enum CertificateType {
     ECDSA = sys::rtcCertificateType_RTC_CERTIFICATE_ECDSA,
     RSA = sys::rtcCertificateType_RTC_CERTIFICATE_RSA,
 }
+
+/// The address families of the candidates used by a connection, see
+/// [`RtcConfig::address_family`].
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum AddressFamily {
+    DualStack,
+    Ipv4Only,
+    Ipv6Only,
+}
+
+impl AddressFamily {
+    pub(crate) fn accepts(&self, cand: &IceCandidate) -> bool {
+        let addr = match cand.address().and_then(|addr| addr.parse::<IpAddr>().ok()) {
+            Some(addr) => addr,
+            None => return true,
+        };
+        match self {
+            AddressFamily::DualStack => true,
+            AddressFamily::Ipv4Only => addr.is_ipv4(),
+            AddressFamily::Ipv6Only => addr.is_ipv6(),
+        }
+    }
+}
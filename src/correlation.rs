@@ -0,0 +1,166 @@
+use std::cell::Cell;
+use std::collections::hash_map::RandomState;
+use std::convert::TryInto;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use lazy_static::lazy_static;
+
+use crate::datachannel::{DataChannelHandler, Message};
+use crate::wrapper::{deliver, forward_handler, skip_header, wrapper_accessors};
+
+const NO_ID: u8 = 0;
+const WITH_ID: u8 = 1;
+const ID_SIZE: usize = 16;
+
+thread_local! {
+    static CURRENT: Cell<Option<CorrelationId>> = const { Cell::new(None) };
+}
+
+lazy_static! {
+    /// The high half of the ids generated by this process.
+    static ref SEED: u64 = random_u64();
+    /// The low half of the next id generated by this process.
+    static ref COUNTER: AtomicU64 = AtomicU64::new(random_u64());
+}
+
+/// A random number from the keys the standard library seeds its hash maps with.
+fn random_u64() -> u64 {
+    RandomState::new().build_hasher().finish()
+}
+
+/// A 128 bits identifier following a message across the WebRTC hop, the same size as
+/// W3C trace ids so that they can be used as such.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CorrelationId(pub [u8; ID_SIZE]);
+
+impl CorrelationId {
+    /// Generates a new id, made of a random seed picked once per process followed by a
+    /// counter starting at a random value.
+    ///
+    /// Ids are thus unique within a process, and between processes unless their seeds
+    /// collide, which is as unlikely as for 64 bits random numbers. They are predictable
+    /// though, and must not be used as secrets.
+    pub fn new() -> Self {
+        let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let mut id = [0; ID_SIZE];
+        id[..8].copy_from_slice(&SEED.to_be_bytes());
+        id[8..].copy_from_slice(&count.to_be_bytes());
+        CorrelationId(id)
+    }
+
+    /// The id of the message being handled on this thread, if it carried one.
+    ///
+    /// It is set while a [`Correlated`] handler delivers a message, and by [`scope`].
+    ///
+    /// [`scope`]: CorrelationId::scope
+    pub fn current() -> Option<Self> {
+        CURRENT.with(|current| current.get())
+    }
+
+    /// Runs `f` with this id as the current one, e.g. to attach it to outgoing messages
+    /// that don't answer a received one.
+    pub fn scope<R>(self, f: impl FnOnce() -> R) -> R {
+        let previous = CURRENT.with(|current| current.replace(Some(self)));
+        let result = f();
+        CURRENT.with(|current| current.set(previous));
+        result
+    }
+
+    /// Returns `msg` framed with this id, ready to be sent to a [`Correlated`] handler.
+    pub fn frame(&self, msg: &[u8]) -> Vec<u8> {
+        let mut framed = Vec::with_capacity(1 + ID_SIZE + msg.len());
+        framed.push(WITH_ID);
+        framed.extend_from_slice(&self.0);
+        framed.extend_from_slice(msg);
+        framed
+    }
+}
+
+impl Default for CorrelationId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Display for CorrelationId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for byte in self.0.iter() {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns `msg` framed with the current [`CorrelationId`], if any, ready to be sent to
+/// a [`Correlated`] handler.
+///
+/// Replies sent from a [`Correlated`] handler thus carry the id of the message they
+/// answer.
+pub fn frame_correlated(msg: &[u8]) -> Vec<u8> {
+    match CorrelationId::current() {
+        Some(id) => id.frame(msg),
+        None => {
+            let mut framed = Vec::with_capacity(1 + msg.len());
+            framed.push(NO_ID);
+            framed.extend_from_slice(msg);
+            framed
+        }
+    }
+}
+
+/// A [`DataChannelHandler`] wrapper reading the correlation header of messages framed
+/// with [`frame_correlated`] or [`CorrelationId::frame`].
+///
+/// The id is the [`CorrelationId::current`] one while the inner handler processes the
+/// message, where it can be attached to a tracing span or log records.
+pub struct Correlated<D> {
+    inner: D,
+}
+
+impl<D> Correlated<D>
+where
+    D: DataChannelHandler,
+{
+    pub fn new(inner: D) -> Self {
+        Correlated { inner }
+    }
+}
+
+wrapper_accessors!(Correlated);
+
+impl<D> DataChannelHandler for Correlated<D>
+where
+    D: DataChannelHandler,
+{
+    forward_handler!(
+        on_created,
+        on_open,
+        on_closed,
+        on_error,
+        on_message,
+        on_buffered_amount_low,
+        on_available,
+        on_quota_exceeded,
+        owned_messages,
+        on_message_owned,
+        subscriptions,
+    );
+
+    fn on_typed_message(&mut self, msg: Message) {
+        let bytes = msg.as_bytes();
+        match bytes.first() {
+            Some(&NO_ID) => deliver(&mut self.inner, skip_header(msg, 1)),
+            Some(&WITH_ID) if bytes.len() > ID_SIZE => {
+                let id = CorrelationId(bytes[1..=ID_SIZE].try_into().unwrap());
+                let msg = skip_header(msg, 1 + ID_SIZE);
+                id.scope(|| deliver(&mut self.inner, msg))
+            }
+            _ => log::warn!(
+                "Dropping message without correlation header ({} bytes)",
+                bytes.len()
+            ),
+        }
+    }
+}
@@ -3,12 +3,22 @@ use std::ffi::{c_void, CStr, CString};
 use std::os::raw::c_char;
 use std::ptr;
 use std::slice;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
 
 use datachannel_sys as sys;
+use parking_lot::Mutex;
 
 use crate::error::{check, Error, Result};
-
-#[derive(Debug, Clone, Default)]
+use crate::fragment::fragment;
+use crate::instrument::{event, Span};
+use crate::peerconnection::ConnectionContext;
+use crate::quota::{Quota, QuotaAction, QuotaCheck, QuotaScope, QuotaTracker};
+use crate::registry;
+use crate::timer;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct Reliability {
     pub unordered: bool,
     pub unreliable: bool,
@@ -19,7 +29,7 @@ pub struct Reliability {
 impl Reliability {
     fn from_raw(raw: sys::rtcReliability) -> Self {
         let max_packet_life_time = u16::try_from(raw.maxPacketLifeTime).unwrap_or(0);
-        let max_retransmits = u16::try_from(raw.maxPacketLifeTime).unwrap_or(0);
+        let max_retransmits = u16::try_from(raw.maxRetransmits).unwrap_or(0);
         Self {
             unordered: raw.unordered,
             unreliable: raw.unreliable,
@@ -65,6 +75,7 @@ pub struct DataChannelInit {
     negotiated: bool,
     manual_stream: bool,
     stream: u16,
+    pub(crate) initial_message: Option<Vec<u8>>,
 }
 
 impl DataChannelInit {
@@ -94,6 +105,16 @@ impl DataChannelInit {
         self
     }
 
+    /// A message sent as soon as the channel opens, from the native open callback and
+    /// before [`DataChannelHandler::on_open`] is called.
+    ///
+    /// It saves waiting for the application to react to `on_open`, which lowers the
+    /// time to first message of latency sensitive handshakes.
+    pub fn initial_message(mut self, msg: &[u8]) -> Self {
+        self.initial_message = Some(msg.to_vec());
+        self
+    }
+
     pub(crate) fn as_raw(&self) -> Result<sys::rtcDataChannelInit> {
         Ok(sys::rtcDataChannelInit {
             reliability: self.reliability.as_raw(),
@@ -105,31 +126,193 @@ impl DataChannelInit {
     }
 }
 
+/// A received message, along with its kind as set by the sender.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Message<'a> {
+    Text(&'a str),
+    Binary(&'a [u8]),
+}
+
+impl<'a> Message<'a> {
+    pub fn as_bytes(&self) -> &'a [u8] {
+        match self {
+            Message::Text(text) => text.as_bytes(),
+            Message::Binary(bytes) => bytes,
+        }
+    }
+
+    pub fn is_text(&self) -> bool {
+        matches!(self, Message::Text(_))
+    }
+}
+
+/// The optional callbacks of a [`DataChannelHandler`] that libdatachannel dispatches,
+/// see [`DataChannelHandler::subscriptions`].
+///
+/// Open and close events are always dispatched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Subscriptions {
+    pub error: bool,
+    pub message: bool,
+    pub buffered_amount_low: bool,
+    pub available: bool,
+}
+
+impl Subscriptions {
+    pub fn all() -> Self {
+        Subscriptions {
+            error: true,
+            message: true,
+            buffered_amount_low: true,
+            available: true,
+        }
+    }
+
+    pub fn none() -> Self {
+        Subscriptions {
+            error: false,
+            message: false,
+            buffered_amount_low: false,
+            available: false,
+        }
+    }
+
+    pub fn error(mut self) -> Self {
+        self.error = true;
+        self
+    }
+
+    pub fn message(mut self) -> Self {
+        self.message = true;
+        self
+    }
+
+    pub fn buffered_amount_low(mut self) -> Self {
+        self.buffered_amount_low = true;
+        self
+    }
+
+    pub fn available(mut self) -> Self {
+        self.available = true;
+        self
+    }
+}
+
+impl Default for Subscriptions {
+    fn default() -> Self {
+        Self::all()
+    }
+}
+
+/// Same limit as browsers, which close channels buffering more than 16 MiB.
+const DEFAULT_MAX_BUFFERED_AMOUNT: usize = 16 * 1024 * 1024;
+
+/// Most messages delivered to an owned-message handler by one available callback, in
+/// case libdatachannel never reports the queue as empty.
+const MAX_OWNED_PER_CALLBACK: usize = 4096;
+
 #[allow(unused_variables)]
 pub trait DataChannelHandler {
+    /// Called once the channel is created, before any other callback, with a handle
+    /// allowing the handler to send messages itself (e.g. replies).
+    fn on_created(&mut self, handle: DataChannelHandle) {}
+
     fn on_open(&mut self) {}
     fn on_closed(&mut self) {}
     fn on_error(&mut self, err: &str) {}
     fn on_message(&mut self, msg: &[u8]) {}
+
+    /// Receives messages along with their kind, the default implementation forwards
+    /// them to [`on_message`].
+    ///
+    /// [`on_message`]: DataChannelHandler::on_message
+    fn on_typed_message(&mut self, msg: Message) {
+        self.on_message(msg.as_bytes())
+    }
+
     fn on_buffered_amount_low(&mut self) {}
     fn on_available(&mut self) {}
+    fn on_quota_exceeded(&mut self, scope: QuotaScope) {}
+
+    /// Whether messages are delivered through [`on_message_owned`] instead of
+    /// [`on_message`], read once when the channel is created.
+    ///
+    /// Owned messages are read from the receive queue of libdatachannel straight into
+    /// their buffer when it signals them as available, instead of being copied out of
+    /// the buffer lent to `on_message`.
+    ///
+    /// [`on_message`]: DataChannelHandler::on_message
+    /// [`on_message_owned`]: DataChannelHandler::on_message_owned
+    fn owned_messages(&self) -> bool {
+        false
+    }
+
+    /// Receives messages in a buffer allocated for each of them, for handlers that keep
+    /// them around (which `Bytes::from` can then wrap without copying). Only called when
+    /// [`owned_messages`] returns true.
+    ///
+    /// [`owned_messages`]: DataChannelHandler::owned_messages
+    fn on_message_owned(&mut self, msg: Vec<u8>) {}
+
+    /// The callbacks to dispatch, read once when the channel is created. Skipping the
+    /// unused ones saves a call into the handler for each event.
+    ///
+    /// Without the message subscription, received messages are neither counted by
+    /// quotas nor delivered, they are kept until read with [`RtcDataChannel::receive`].
+    fn subscriptions(&self) -> Subscriptions {
+        Subscriptions::all()
+    }
 }
 
 pub struct RtcDataChannel<D> {
     id: i32,
     dc_handler: D,
+    handle: DataChannelHandle,
+    quota: Mutex<Option<QuotaTracker>>,
+    conn: Arc<ConnectionContext>,
+    initial_message: Option<Vec<u8>>,
+    /// Shared with the timer task deleting the channel after [`QuotaAction::Close`].
+    deleted: Arc<AtomicBool>,
+    closed_notified: bool,
+    /// Whether the handler subscribed to `on_available`.
+    available: bool,
+    /// Whether messages are read from the receive queue for `on_message_owned`.
+    owned: bool,
 }
 
 impl<D> RtcDataChannel<D>
 where
     D: DataChannelHandler + Send,
 {
-    pub(crate) fn new(id: i32, dc_handler: D) -> Result<Box<Self>> {
+    pub(crate) fn new(
+        id: i32,
+        dc_handler: D,
+        conn: Arc<ConnectionContext>,
+        initial_message: Option<Vec<u8>>,
+    ) -> Result<Box<Self>> {
         unsafe {
-            let mut rtc_dc = Box::new(RtcDataChannel { id, dc_handler });
+            let mut rtc_dc = Box::new(RtcDataChannel {
+                id,
+                dc_handler,
+                handle: DataChannelHandle {
+                    id,
+                    max_buffered_amount: Arc::new(AtomicUsize::new(DEFAULT_MAX_BUFFERED_AMOUNT)),
+                    span: Span::channel(&conn.span, id),
+                },
+                quota: Mutex::new(None),
+                conn,
+                initial_message,
+                deleted: Arc::new(AtomicBool::new(false)),
+                closed_notified: false,
+                available: false,
+                owned: false,
+            });
+            crate::object_created();
             let ptr = &mut *rtc_dc;
 
             sys::rtcSetUserPointer(id, ptr as *mut _ as *mut c_void);
+            let handle = rtc_dc.handle.clone();
+            rtc_dc.dc_handler.on_created(handle);
 
             check(sys::rtcSetOpenCallback(
                 id,
@@ -141,109 +324,357 @@ where
                 Some(RtcDataChannel::<D>::closed_cb),
             ))?;
 
-            check(sys::rtcSetErrorCallback(
-                id,
-                Some(RtcDataChannel::<D>::error_cb),
-            ))?;
+            let subscriptions = rtc_dc.dc_handler.subscriptions();
 
-            check(sys::rtcSetMessageCallback(
-                id,
-                Some(RtcDataChannel::<D>::message_cb),
-            ))?;
+            if subscriptions.error {
+                check(sys::rtcSetErrorCallback(
+                    id,
+                    Some(RtcDataChannel::<D>::error_cb),
+                ))?;
+            }
 
-            check(sys::rtcSetBufferedAmountLowCallback(
-                id,
-                Some(RtcDataChannel::<D>::buffered_amount_low_cb),
-            ))?;
+            // Owned messages are left in the receive queue, and read once available
+            rtc_dc.owned = subscriptions.message && rtc_dc.dc_handler.owned_messages();
+            if subscriptions.message && !rtc_dc.owned {
+                check(sys::rtcSetMessageCallback(
+                    id,
+                    Some(RtcDataChannel::<D>::message_cb),
+                ))?;
+            }
 
-            check(sys::rtcSetAvailableCallback(
-                id,
-                Some(RtcDataChannel::<D>::available_cb),
-            ))?;
+            if subscriptions.buffered_amount_low {
+                check(sys::rtcSetBufferedAmountLowCallback(
+                    id,
+                    Some(RtcDataChannel::<D>::buffered_amount_low_cb),
+                ))?;
+            }
+
+            // Without the message callback, available messages keep the connection alive
+            rtc_dc.available = subscriptions.available;
+            let liveness = !subscriptions.message && rtc_dc.conn.watches_liveness();
+            if subscriptions.available || rtc_dc.owned || liveness {
+                check(sys::rtcSetAvailableCallback(
+                    id,
+                    Some(RtcDataChannel::<D>::available_cb),
+                ))?;
+            }
+
+            registry::add_channel(rtc_dc.conn.id, id, || rtc_dc.label());
 
             Ok(rtc_dc)
         }
     }
 
-    unsafe extern "C" fn open_cb(_: i32, ptr: *mut c_void) {
+    unsafe extern "C" fn open_cb(id: i32, ptr: *mut c_void) {
         let rtc_dc = &mut *(ptr as *mut RtcDataChannel<D>);
+        let _timer = rtc_dc.conn.timer();
+        registry::set_channel_open(id, true);
+
+        let _span = rtc_dc.handle.span.entered();
+        event!(INFO, "Data channel open");
+        if let Some(msg) = rtc_dc.initial_message.take() {
+            if let Err(err) = rtc_dc.handle.send(&msg) {
+                log::error!(
+                    "Couldn't send initial message on RtcDataChannel id={}: {}",
+                    id,
+                    err
+                );
+            }
+        }
         rtc_dc.dc_handler.on_open()
     }
 
-    unsafe extern "C" fn closed_cb(_: i32, ptr: *mut c_void) {
+    unsafe extern "C" fn closed_cb(id: i32, ptr: *mut c_void) {
         let rtc_dc = &mut *(ptr as *mut RtcDataChannel<D>);
-        rtc_dc.dc_handler.on_closed()
+        let _timer = rtc_dc.conn.timer();
+        registry::set_channel_open(id, false);
+
+        let _span = rtc_dc.handle.span.entered();
+        event!(INFO, "Data channel closed");
+        rtc_dc.notify_closed()
+    }
+
+    fn notify_closed(&mut self) {
+        if !self.closed_notified {
+            self.closed_notified = true;
+            self.dc_handler.on_closed()
+        }
     }
 
     unsafe extern "C" fn error_cb(_: i32, err: *const c_char, ptr: *mut c_void) {
         let rtc_dc = &mut *(ptr as *mut RtcDataChannel<D>);
-        let err = CStr::from_ptr(err).to_string_lossy();
+        let _timer = rtc_dc.conn.timer();
+        let err = crate::ffi_str(err);
+
+        let _span = rtc_dc.handle.span.entered();
+        event!(WARN, %err, "Data channel error");
         rtc_dc.dc_handler.on_error(&err)
     }
 
-    unsafe extern "C" fn message_cb(_: i32, msg: *const c_char, size: i32, ptr: *mut c_void) {
+    unsafe extern "C" fn message_cb(id: i32, msg: *const c_char, size: i32, ptr: *mut c_void) {
         let rtc_dc = &mut *(ptr as *mut RtcDataChannel<D>);
-        let msg = if size < 0 {
+        let _timer = rtc_dc.conn.timer();
+        let text = size < 0;
+        let msg = if text {
             CStr::from_ptr(msg).to_bytes()
         } else {
             slice::from_raw_parts(msg as *const u8, size as usize)
         };
-        rtc_dc.dc_handler.on_message(msg)
+        registry::record_received(id, msg.len());
+        rtc_dc.conn.record_received();
+
+        let _span = rtc_dc.handle.span.entered();
+        event!(TRACE, size = msg.len(), text, "Received message");
+        if !rtc_dc.enforce_quotas(msg.len()) {
+            return;
+        }
+        match std::str::from_utf8(msg) {
+            Ok(msg) if text => rtc_dc.dc_handler.on_typed_message(Message::Text(msg)),
+            Err(err) if text => {
+                log::warn!(
+                    "Received invalid text message on RtcDataChannel id={}: {}",
+                    id,
+                    err
+                );
+                rtc_dc.dc_handler.on_typed_message(Message::Binary(msg))
+            }
+            _ => rtc_dc.dc_handler.on_typed_message(Message::Binary(msg)),
+        }
+    }
+
+    /// Records a received message against the channel and connection quotas, returns
+    /// whether it should be delivered.
+    fn enforce_quotas(&mut self, size: usize) -> bool {
+        if self.closed_notified {
+            // Waiting for the deletion scheduled by `close_deferred`
+            return false;
+        }
+        let checks = [
+            (
+                QuotaScope::Channel,
+                self.quota.lock().as_mut().map(|q| q.record(size)),
+            ),
+            (
+                QuotaScope::Connection,
+                self.conn.quota.lock().as_mut().map(|q| q.record(size)),
+            ),
+        ];
+
+        let mut deliver = true;
+        for (scope, check) in checks.iter() {
+            if let Some(QuotaCheck::Exceeded { action, notify }) = check {
+                if *notify {
+                    self.dc_handler.on_quota_exceeded(*scope);
+                }
+                match action {
+                    QuotaAction::Notify => (),
+                    QuotaAction::Drop => deliver = false,
+                    QuotaAction::Close => {
+                        match scope {
+                            QuotaScope::Channel => self.close_deferred(),
+                            QuotaScope::Connection => self.conn.close_deferred(),
+                        }
+                        return false;
+                    }
+                }
+            }
+        }
+        deliver
+    }
+
+    /// Closes the channel from one of its callbacks, in which it can't be deleted: the
+    /// handler is notified right away, and the channel deleted from the timer thread.
+    fn close_deferred(&mut self) {
+        let _span = self.handle.span.entered();
+        event!(INFO, "Closing data channel, receive quota exceeded");
+
+        self.notify_closed();
+        let (id, deleted) = (self.id, self.deleted.clone());
+        timer::schedule(Instant::now(), move || delete_channel(id, &deleted));
+    }
+
+    /// Sets the receive quota of this channel, `None` removes it.
+    ///
+    /// It is enforced in addition to the quota of the connection, see
+    /// [`RtcPeerConnection::set_receive_quota`].
+    ///
+    /// [`RtcPeerConnection::set_receive_quota`]: crate::RtcPeerConnection::set_receive_quota
+    pub fn set_receive_quota(&mut self, quota: Option<Quota>) {
+        *self.quota.lock() = quota.map(QuotaTracker::new);
     }
 
     unsafe extern "C" fn buffered_amount_low_cb(_: i32, ptr: *mut c_void) {
         let rtc_dc = &mut *(ptr as *mut RtcDataChannel<D>);
+        let _timer = rtc_dc.conn.timer();
         rtc_dc.dc_handler.on_buffered_amount_low()
     }
 
     unsafe extern "C" fn available_cb(_: i32, ptr: *mut c_void) {
         let rtc_dc = &mut *(ptr as *mut RtcDataChannel<D>);
-        rtc_dc.dc_handler.on_available()
+        let _timer = rtc_dc.conn.timer();
+        if rtc_dc.owned {
+            rtc_dc.deliver_owned();
+        } else {
+            rtc_dc.conn.record_received();
+        }
+        if rtc_dc.available {
+            rtc_dc.dc_handler.on_available()
+        }
+    }
+
+    /// Delivers the queued messages to `on_message_owned`, libdatachannel only signals
+    /// the queue as available once it stops being empty. Messages left past
+    /// [`MAX_OWNED_PER_CALLBACK`] stay queued for [`receive`].
+    ///
+    /// [`receive`]: RtcDataChannel::receive
+    fn deliver_owned(&mut self) {
+        // Stops once closed by a quota, the channel is deleted shortly after
+        for _ in 0..MAX_OWNED_PER_CALLBACK {
+            if self.closed_notified {
+                return;
+            }
+            let msg = match self.receive_buffer() {
+                Ok(Some(msg)) => msg,
+                Ok(None) => return,
+                Err(err) => {
+                    log::error!(
+                        "Couldn't receive message on RtcDataChannel id={}: {}",
+                        self.id,
+                        err
+                    );
+                    return;
+                }
+            };
+            registry::record_received(self.id, msg.len());
+            self.conn.record_received();
+
+            let _span = self.handle.span.entered();
+            event!(TRACE, size = msg.len(), "Received message");
+            if self.enforce_quotas(msg.len()) {
+                self.dc_handler.on_message_owned(msg)
+            }
+        }
+    }
+
+    pub(crate) fn handler(&self) -> &D {
+        &self.dc_handler
+    }
+
+    /// Returns a cloneable handle usable from other threads, see [`DataChannelHandle`].
+    pub fn handle(&self) -> DataChannelHandle {
+        self.handle.clone()
     }
 
     pub fn send(&mut self, msg: &[u8]) -> Result<()> {
-        check(unsafe {
-            sys::rtcSendMessage(self.id, msg.as_ptr() as *const c_char, msg.len() as i32)
-        })
-        .map(|_| ())
+        self.handle.send(msg)
+    }
+
+    /// Sends `msg` unless it would exceed the maximum buffered amount, see
+    /// [`DataChannelHandle::try_send`].
+    pub fn try_send(&mut self, msg: &[u8]) -> Result<()> {
+        self.handle.try_send(msg)
+    }
+
+    pub fn set_max_buffered_amount(&mut self, amount: usize) {
+        self.handle.set_max_buffered_amount(amount)
+    }
+
+    /// Sends a text message, received as a string rather than an `ArrayBuffer` by
+    /// browsers.
+    pub fn send_text(&mut self, msg: &str) -> Result<()> {
+        self.handle.send_text(msg)
+    }
+
+    /// Sends a binary message, same as [`send`].
+    ///
+    /// [`send`]: RtcDataChannel::send
+    pub fn send_binary(&mut self, msg: &[u8]) -> Result<()> {
+        self.handle.send_binary(msg)
+    }
+
+    /// Sends several messages in order, see [`DataChannelHandle::send_all`].
+    pub fn send_all<M: AsRef<[u8]>>(&mut self, msgs: &[M]) -> Result<()> {
+        self.handle.send_all(msgs)
+    }
+
+    /// Sends `msg` split into fragments no larger than [`max_message_size`], to be
+    /// reassembled by a [`Fragmented`] handler on the remote side.
+    ///
+    /// Fragments are sent with [`send_all`], and [`Error::PartiallySent`] counts the
+    /// fragments sent. The remote handler can't reassemble the following messages after
+    /// such a failure, the channel should be closed.
+    ///
+    /// [`max_message_size`]: RtcDataChannel::max_message_size
+    /// [`send_all`]: DataChannelHandle::send_all
+    /// [`Fragmented`]: crate::Fragmented
+    pub fn send_fragmented(&mut self, msg: &[u8]) -> Result<()> {
+        let fragments = fragment(msg, self.max_message_size())?;
+        self.handle.send_all(&fragments)
+    }
+
+    /// The largest message that can be sent on this channel, as negotiated with the
+    /// remote peer. Larger messages fail to be sent.
+    pub fn max_message_size(&self) -> usize {
+        self.conn.max_message_size()
     }
 
     pub fn receive(&mut self) -> Result<Option<Vec<u8>>> {
-        let mut size = 0 as i32;
-        let buf_size = check(unsafe {
-            sys::rtcReceiveMessage(self.id, ptr::null_mut() as *mut c_char, &mut size)
-        })
-        .expect("Couldn't get buffer size") as usize;
+        let msg = self.receive_buffer()?;
+        if msg.is_some() {
+            self.conn.record_received();
+        }
+        Ok(msg)
+    }
 
-        let mut buf = vec![0; buf_size];
-        unsafe {
-            match check(sys::rtcReceiveMessage(
-                self.id,
-                buf.as_mut_ptr() as *mut c_char,
-                &mut size,
-            )) {
-                Ok(_) => {
-                    let msg = if size < 0 {
-                        CStr::from_ptr(buf.as_ptr()).to_bytes()
-                    } else {
-                        slice::from_raw_parts(
-                            buf[..size as usize].as_ptr() as *const u8,
-                            size as usize,
-                        )
-                    };
-                    Ok(Some(msg.to_vec()))
-                }
-                Err(Error::NotAvailable) => Ok(None),
-                Err(err) => Err(err),
+    /// Reads the next message of the receive queue into a buffer of its size.
+    fn receive_buffer(&self) -> Result<Option<Vec<u8>>> {
+        // The next message is no larger than the queued bytes, nor than the local maximum
+        let max_size = self
+            .available_amount()
+            .min(self.conn.local_max_message_size());
+        // Plus the nul terminating text messages
+        let mut buf = vec![0u8; max_size + 1];
+        let mut size = i32::try_from(buf.len()).unwrap_or(i32::MAX);
+        match check(unsafe {
+            sys::rtcReceiveMessage(self.id, buf.as_mut_ptr() as *mut c_char, &mut size)
+        }) {
+            Ok(_) => {
+                // Text messages are nul terminated, binary ones are `size` bytes long
+                let len = if size < 0 {
+                    buf.iter().position(|&b| b == 0).unwrap_or(buf.len())
+                } else {
+                    size as usize
+                };
+                buf.truncate(len);
+                Ok(Some(buf))
             }
+            Err(Error::NotAvailable) => Ok(None),
+            Err(err) => Err(err),
         }
     }
 
+    /// The libdatachannel id of the data channel, as listed by the [`Registry`].
+    ///
+    /// [`Registry`]: crate::Registry
+    pub fn id(&self) -> i32 {
+        self.id
+    }
+
     pub fn label(&self) -> String {
-        let buf_size = check(unsafe {
+        let buf_size = match check(unsafe {
             sys::rtcGetDataChannelLabel(self.id, ptr::null_mut() as *mut c_char, 0)
-        })
-        .expect("Couldn't get buffer size") as usize;
+        }) {
+            Ok(buf_size) => buf_size as usize,
+            Err(err) => {
+                log::warn!(
+                    "Couldn't get label for RtcDataChannel id={} {:p}, {}",
+                    self.id,
+                    self,
+                    err
+                );
+                return String::default();
+            }
+        };
 
         let mut buf = vec![0; buf_size];
         match check(unsafe {
@@ -275,10 +706,20 @@ where
     }
 
     pub fn protocol(&self) -> Option<String> {
-        let buf_size = check(unsafe {
+        let buf_size = match check(unsafe {
             sys::rtcGetDataChannelProtocol(self.id, ptr::null_mut() as *mut c_char, 0)
-        })
-        .expect("Couldn't get buffer size") as usize;
+        }) {
+            Ok(buf_size) => buf_size as usize,
+            Err(err) => {
+                log::warn!(
+                    "Couldn't get protocol for RtcDataChannel id={} {:p}, {}",
+                    self.id,
+                    self,
+                    err
+                );
+                return None;
+            }
+        };
 
         let mut buf = vec![0; buf_size];
         match check(unsafe {
@@ -313,7 +754,9 @@ where
         }
     }
 
-    pub fn reliability(&self) -> Reliability {
+    /// The reliability parameters negotiated for the data channel, which may differ from
+    /// the requested ones on the receiving side.
+    pub fn reliability(&self) -> Result<Reliability> {
         let mut reliability = sys::rtcReliability {
             unordered: false,
             unreliable: false,
@@ -321,15 +764,14 @@ where
             maxRetransmits: 0,
         };
 
-        check(unsafe { sys::rtcGetDataChannelReliability(self.id, &mut reliability) })
-            .expect("Couldn't get RtcDataChannel reliability");
-
-        Reliability::from_raw(reliability)
+        check(unsafe { sys::rtcGetDataChannelReliability(self.id, &mut reliability) })?;
+        Ok(Reliability::from_raw(reliability))
     }
 
-    pub fn stream(&self) -> usize {
-        check(unsafe { sys::rtcGetDataChannelStream(self.id) })
-            .expect("Couldn't get RtcDataChannel stream") as usize
+    /// The SCTP stream id of the data channel, shared by both peers.
+    pub fn stream(&self) -> Result<usize> {
+        let stream = check(unsafe { sys::rtcGetDataChannelStream(self.id) })?;
+        Ok(stream as usize)
     }
 
     /// Number of bytes currently queued to be sent over the data channel.
@@ -338,18 +780,7 @@ where
     ///
     /// [`available_amount`]: RtcDataChannel::available_amount
     pub fn buffered_amount(&self) -> usize {
-        match check(unsafe { sys::rtcGetBufferedAmount(self.id) }) {
-            Ok(amount) => amount as usize,
-            Err(err) => {
-                log::error!(
-                    "Couldn't get buffered_amount for RtcDataChannel id={} {:p}, {}",
-                    self.id,
-                    self,
-                    err
-                );
-                0
-            }
-        }
+        self.handle.buffered_amount()
     }
 
     /// Sets the lower threshold of `buffered_amount`.
@@ -363,9 +794,7 @@ where
     /// [`buffered_amount`]: RtcDataChannel::buffered_amount
     /// [`on_bufferd_amount_low`]: RtcDataChannel::on_bufferd_amount_low
     pub fn set_buffered_amount_low_threshold(&mut self, amount: usize) -> Result<()> {
-        let amount = i32::try_from(amount).map_err(|_| Error::InvalidArg)?;
-        check(unsafe { sys::rtcSetBufferedAmountLowThreshold(self.id, amount) })?;
-        Ok(())
+        self.handle.set_buffered_amount_low_threshold(amount)
     }
 
     /// Number of bytes currently queued to be consumed from the data channel.
@@ -373,14 +802,148 @@ where
     /// This method is the counterpart of [`buffered_amount`].
     ///
     /// [`buffered_amount`]: RtcDataChannel::buffered_amount
+    pub fn available_amount(&self) -> usize {
+        self.handle.available_amount()
+    }
+}
+
+/// A cloneable, `Send + Sync` handle to a [`RtcDataChannel`].
+///
+/// It allows sending from several threads at once without going through the owner of
+/// the channel, and doesn't keep the channel alive: once the [`RtcDataChannel`] is
+/// dropped, its methods return [`Error::InvalidArg`].
+#[derive(Debug, Clone)]
+pub struct DataChannelHandle {
+    id: i32,
+    max_buffered_amount: Arc<AtomicUsize>,
+    span: Span,
+}
+
+impl DataChannelHandle {
+    pub(crate) fn id(&self) -> i32 {
+        self.id
+    }
+
+    pub fn send(&self, msg: &[u8]) -> Result<()> {
+        let size = i32::try_from(msg.len()).map_err(|_| Error::TooLarge(msg.len()))?;
+        check(unsafe { sys::rtcSendMessage(self.id, msg.as_ptr() as *const c_char, size) })?;
+        self.record_sent(msg.len());
+        Ok(())
+    }
+
+    /// Sends a text message, which can't contain nul characters.
+    pub fn send_text(&self, msg: &str) -> Result<()> {
+        let text = CString::new(msg)?;
+        // A negative size tells libdatachannel the message is a nul terminated string
+        check(unsafe { sys::rtcSendMessage(self.id, text.as_ptr(), -1) })?;
+        self.record_sent(msg.len());
+        Ok(())
+    }
+
+    pub fn send_binary(&self, msg: &[u8]) -> Result<()> {
+        self.send(msg)
+    }
+
+    /// Sends several messages in order. This is a convenience loop, each message is a
+    /// separate libdatachannel call as its API has no batch send.
+    ///
+    /// Sizes are checked before sending anything, so [`Error::TooLarge`] means that no
+    /// message was sent. Failing to send a message (e.g. on a deleted channel) returns
+    /// [`Error::PartiallySent`] with the number of messages sent before it.
+    pub fn send_all<M: AsRef<[u8]>>(&self, msgs: &[M]) -> Result<()> {
+        for msg in msgs {
+            let len = msg.as_ref().len();
+            i32::try_from(len).map_err(|_| Error::TooLarge(len))?;
+        }
+        let mut total = 0;
+        for (sent, msg) in msgs.iter().enumerate() {
+            let msg = msg.as_ref();
+            if let Err(err) = check(unsafe {
+                sys::rtcSendMessage(self.id, msg.as_ptr() as *const c_char, msg.len() as i32)
+            }) {
+                if sent > 0 {
+                    self.record_sent_many(sent, total);
+                }
+                return Err(Error::PartiallySent {
+                    sent,
+                    error: Box::new(err),
+                });
+            }
+            total += msg.len();
+        }
+        self.record_sent_many(msgs.len(), total);
+        Ok(())
+    }
+
+    /// Sends `msg` unless the amount of buffered outgoing bytes would exceed the
+    /// maximum buffered amount, in which case [`Error::BufferFull`] is returned with the
+    /// current [`buffered_amount`].
+    ///
+    /// libdatachannel never rejects messages, [`send`] keeps buffering them when the
+    /// peer can't keep up. With `try_send`, a sender can instead pause on
+    /// `BufferFull` and resume on [`DataChannelHandler::on_buffered_amount_low`], given a
+    /// low threshold set below the maximum with [`set_buffered_amount_low_threshold`].
+    ///
+    /// [`buffered_amount`]: DataChannelHandle::buffered_amount
+    /// [`send`]: DataChannelHandle::send
+    /// [`set_buffered_amount_low_threshold`]: DataChannelHandle::set_buffered_amount_low_threshold
+    pub fn try_send(&self, msg: &[u8]) -> Result<()> {
+        let buffered = self.buffered_amount();
+        if buffered.saturating_add(msg.len()) > self.max_buffered_amount() {
+            return Err(Error::BufferFull { buffered });
+        }
+        self.send(msg)
+    }
+
+    /// The limit used by `try_send`, 16 MiB by default. It is shared by all the handles
+    /// of a channel.
+    pub fn max_buffered_amount(&self) -> usize {
+        self.max_buffered_amount.load(Ordering::Relaxed)
+    }
+
+    pub fn set_max_buffered_amount(&self, amount: usize) {
+        self.max_buffered_amount.store(amount, Ordering::Relaxed)
+    }
+
+    fn record_sent(&self, size: usize) {
+        let _span = self.span.entered();
+        event!(TRACE, size, "Sent message");
+        registry::record_sent(self.id, 1, size);
+    }
+
+    fn record_sent_many(&self, count: usize, size: usize) {
+        let _span = self.span.entered();
+        event!(TRACE, count, size, "Sent messages");
+        registry::record_sent(self.id, count, size);
+    }
+
+    pub fn buffered_amount(&self) -> usize {
+        match check(unsafe { sys::rtcGetBufferedAmount(self.id) }) {
+            Ok(amount) => amount as usize,
+            Err(err) => {
+                log::error!(
+                    "Couldn't get buffered_amount for RtcDataChannel id={}, {}",
+                    self.id,
+                    err
+                );
+                0
+            }
+        }
+    }
+
+    pub fn set_buffered_amount_low_threshold(&self, amount: usize) -> Result<()> {
+        let amount = i32::try_from(amount).map_err(|_| Error::InvalidArg)?;
+        check(unsafe { sys::rtcSetBufferedAmountLowThreshold(self.id, amount) })?;
+        Ok(())
+    }
+
     pub fn available_amount(&self) -> usize {
         match check(unsafe { sys::rtcGetAvailableAmount(self.id) }) {
             Ok(amount) => amount as usize,
             Err(err) => {
                 log::error!(
-                    "Couldn't get available_amount for RtcDataChannel id={} {:p}, {}",
+                    "Couldn't get available_amount for RtcDataChannel id={}, {}",
                     self.id,
-                    self,
                     err
                 );
                 0
@@ -389,16 +952,28 @@ where
     }
 }
 
+impl<D> RtcDataChannel<D> {
+    /// Deletes the underlying data channel, only the first call has an effect.
+    fn delete(&self) {
+        delete_channel(self.id, &self.deleted)
+    }
+}
+
+/// Deletes the channel in libdatachannel, which stops its callbacks. Only the first call
+/// has an effect.
+fn delete_channel(id: i32, deleted: &AtomicBool) {
+    if deleted.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    registry::remove_channel(id);
+    if let Err(err) = check(unsafe { sys::rtcDeleteDataChannel(id) }) {
+        log::error!("Error while deleting RtcDataChannel id={}: {}", id, err);
+    }
+}
+
 impl<D> Drop for RtcDataChannel<D> {
     fn drop(&mut self) {
-        match check(unsafe { sys::rtcDeleteDataChannel(self.id) }) {
-            Err(err) => log::error!(
-                "Error while dropping RtcDataChannel id={} {:p}: {}",
-                self.id,
-                self,
-                err
-            ),
-            _ => (),
-        }
+        self.delete();
+        crate::object_dropped();
     }
 }
@@ -0,0 +1,115 @@
+use webrtc_sdp::attribute_type::SdpAttribute;
+
+use crate::peerconnection::SessionDescription;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MediaDirection {
+    SendRecv,
+    SendOnly,
+    RecvOnly,
+    Inactive,
+}
+
+/// A media section (m-line) of a session description.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MediaSection {
+    /// The `a=mid` value, or the index of the m-line when it has none.
+    pub mid: String,
+    /// The media type, i.e. `audio`, `video` or `application` for data channels.
+    pub kind: String,
+    pub direction: MediaDirection,
+    /// Whether the m-line has a zero port, meaning it has been rejected or stopped.
+    pub rejected: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DirectionChange {
+    pub mid: String,
+    pub old: MediaDirection,
+    pub new: MediaDirection,
+}
+
+/// Structured changes between two session descriptions, see [`SessionDescription::diff`].
+///
+/// Data channels are negotiated in-band, so a new channel only shows up here when it
+/// causes the `application` media section to be added.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SdpDiff {
+    pub added: Vec<MediaSection>,
+    pub removed: Vec<MediaSection>,
+    pub direction_changes: Vec<DirectionChange>,
+}
+
+impl SdpDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.direction_changes.is_empty()
+    }
+}
+
+impl SessionDescription {
+    /// Returns the media sections of this description.
+    pub fn media_sections(&self) -> Vec<MediaSection> {
+        self.sdp
+            .media
+            .iter()
+            .enumerate()
+            .map(|(index, media)| {
+                let mut section = MediaSection {
+                    mid: index.to_string(),
+                    kind: media.get_type().to_string(),
+                    direction: MediaDirection::SendRecv,
+                    rejected: media.get_port() == 0,
+                };
+                for attribute in media.get_attributes() {
+                    match attribute {
+                        SdpAttribute::Mid(mid) => section.mid = mid.clone(),
+                        SdpAttribute::Sendrecv => section.direction = MediaDirection::SendRecv,
+                        SdpAttribute::Sendonly => section.direction = MediaDirection::SendOnly,
+                        SdpAttribute::Recvonly => section.direction = MediaDirection::RecvOnly,
+                        SdpAttribute::Inactive => section.direction = MediaDirection::Inactive,
+                        _ => (),
+                    }
+                }
+                section
+            })
+            .collect()
+    }
+
+    /// Computes what changed from `previous` to this description, typically the former
+    /// and the new remote description during a renegotiation.
+    ///
+    /// Rejected media sections are considered absent, so a section whose port is set to
+    /// zero is reported as removed.
+    pub fn diff(&self, previous: &SessionDescription) -> SdpDiff {
+        let active = |sections: Vec<MediaSection>| {
+            sections
+                .into_iter()
+                .filter(|s| !s.rejected)
+                .collect::<Vec<_>>()
+        };
+        let old = active(previous.media_sections());
+        let new = active(self.media_sections());
+        let mut diff = SdpDiff::default();
+
+        for section in new.iter() {
+            match old.iter().find(|s| s.mid == section.mid) {
+                Some(prev) if prev.direction != section.direction => {
+                    diff.direction_changes.push(DirectionChange {
+                        mid: section.mid.clone(),
+                        old: prev.direction,
+                        new: section.direction,
+                    })
+                }
+                Some(_) => (),
+                None => diff.added.push(section.clone()),
+            }
+        }
+
+        diff.removed = old
+            .into_iter()
+            .filter(|s| !new.iter().any(|n| n.mid == s.mid))
+            .collect();
+
+        diff
+    }
+}
@@ -0,0 +1,172 @@
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+
+use crate::datachannel::{
+    DataChannelHandle, DataChannelHandler, Message, RtcDataChannel, Subscriptions,
+};
+use crate::diff::SdpDiff;
+use crate::peerconnection::{
+    CandidatePair, ConnectionState, FailureReason, GatheringState, IceCandidate,
+    PeerConnectionHandler, SessionDescription, SignalingState,
+};
+use crate::quota::QuotaScope;
+
+pub type Task = Box<dyn FnOnce() + Send>;
+
+/// Runs the callbacks of [`Dispatched`] handlers, e.g. by sending them to a thread or an
+/// executor owned by the application.
+///
+/// Implemented for closures, such as `move |task| tx.send(task).unwrap()`.
+pub trait Dispatcher: Send + Sync {
+    fn dispatch(&self, task: Task);
+}
+
+impl<F> Dispatcher for F
+where
+    F: Fn(Task) + Send + Sync,
+{
+    fn dispatch(&self, task: Task) {
+        self(task)
+    }
+}
+
+/// A handler wrapper marshalling callbacks to a [`Dispatcher`] instead of running them
+/// on the threads of libdatachannel.
+///
+/// Handlers can then use their connection and data channels freely, without risking
+/// deadlocks by re-entering libdatachannel from its own callbacks. Callbacks are
+/// dispatched in order, messages are copied to do so.
+///
+/// Wrapping a [`PeerConnectionHandler`] only dispatches the connection events, its data
+/// channels handlers (`PeerConnectionHandler::DCH`) should be `Dispatched` as well.
+/// `data_channel_handler` is the exception, it is called synchronously.
+pub struct Dispatched<H> {
+    inner: Arc<Mutex<H>>,
+    dispatcher: Arc<dyn Dispatcher>,
+}
+
+impl<H> Dispatched<H>
+where
+    H: Send + 'static,
+{
+    pub fn new(inner: H, dispatcher: Arc<dyn Dispatcher>) -> Self {
+        Dispatched {
+            inner: Arc::new(Mutex::new(inner)),
+            dispatcher,
+        }
+    }
+
+    /// The wrapped handler, shared with the dispatched callbacks.
+    pub fn inner(&self) -> Arc<Mutex<H>> {
+        self.inner.clone()
+    }
+
+    fn dispatch(&self, f: impl FnOnce(&mut H) + Send + 'static) {
+        let inner = self.inner.clone();
+        self.dispatcher
+            .dispatch(Box::new(move || f(&mut inner.lock())))
+    }
+}
+
+impl<P> PeerConnectionHandler for Dispatched<P>
+where
+    P: PeerConnectionHandler + Send + 'static,
+    P::DCH: Send + 'static,
+{
+    type DCH = P::DCH;
+
+    fn data_channel_handler(&mut self) -> Self::DCH {
+        self.inner.lock().data_channel_handler()
+    }
+
+    fn on_description(&mut self, sess_desc: SessionDescription) {
+        self.dispatch(move |h| h.on_description(sess_desc))
+    }
+
+    fn on_description_diff(&mut self, diff: SdpDiff) {
+        self.dispatch(move |h| h.on_description_diff(diff))
+    }
+
+    fn on_candidate(&mut self, cand: IceCandidate) {
+        self.dispatch(move |h| h.on_candidate(cand))
+    }
+
+    fn on_connection_state_change(&mut self, state: ConnectionState) {
+        self.dispatch(move |h| h.on_connection_state_change(state))
+    }
+
+    fn on_gathering_state_change(&mut self, state: GatheringState) {
+        self.dispatch(move |h| h.on_gathering_state_change(state))
+    }
+
+    fn on_signaling_state_change(&mut self, state: SignalingState) {
+        self.dispatch(move |h| h.on_signaling_state_change(state))
+    }
+
+    fn on_candidate_pair_change(&mut self, old: Option<CandidatePair>, new: CandidatePair) {
+        self.dispatch(move |h| h.on_candidate_pair_change(old, new))
+    }
+
+    fn on_failure(&mut self, reason: FailureReason) {
+        self.dispatch(move |h| h.on_failure(reason))
+    }
+
+    fn on_data_channel(&mut self, data_channel: Box<RtcDataChannel<Self::DCH>>) {
+        self.dispatch(move |h| h.on_data_channel(data_channel))
+    }
+}
+
+impl<D> DataChannelHandler for Dispatched<D>
+where
+    D: DataChannelHandler + Send + 'static,
+{
+    fn on_created(&mut self, handle: DataChannelHandle) {
+        self.inner.lock().on_created(handle)
+    }
+
+    fn on_open(&mut self) {
+        self.dispatch(|h| h.on_open())
+    }
+
+    fn on_closed(&mut self) {
+        self.dispatch(|h| h.on_closed())
+    }
+
+    fn on_error(&mut self, err: &str) {
+        let err = err.to_string();
+        self.dispatch(move |h| h.on_error(&err))
+    }
+
+    fn on_typed_message(&mut self, msg: Message) {
+        let text = msg.is_text();
+        let msg = msg.as_bytes().to_vec();
+        self.dispatch(move |h| {
+            if h.owned_messages() {
+                h.on_message_owned(msg)
+            } else if text {
+                // Valid UTF-8, as checked before calling `on_typed_message`
+                let text = String::from_utf8_lossy(&msg);
+                h.on_typed_message(Message::Text(&text))
+            } else {
+                h.on_typed_message(Message::Binary(&msg))
+            }
+        })
+    }
+
+    fn on_buffered_amount_low(&mut self) {
+        self.dispatch(|h| h.on_buffered_amount_low())
+    }
+
+    fn on_available(&mut self) {
+        self.dispatch(|h| h.on_available())
+    }
+
+    fn on_quota_exceeded(&mut self, scope: QuotaScope) {
+        self.dispatch(move |h| h.on_quota_exceeded(scope))
+    }
+
+    fn subscriptions(&self) -> Subscriptions {
+        self.inner.lock().subscriptions()
+    }
+}
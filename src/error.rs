@@ -1,5 +1,7 @@
 use std::fmt::{self, Display};
 
+use datachannel_sys as sys;
+
 pub type Result<T> = std::result::Result<T, Error>;
 
 pub fn check(code: i32) -> Result<i32> {
@@ -10,24 +12,60 @@ pub fn check(code: i32) -> Result<i32> {
     }
 }
 
-#[derive(Debug)]
+/// Errors returned by this crate, mapped from libdatachannel return codes when relevant.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
 pub enum Error {
+    /// Invalid argument (`RTC_ERR_INVALID`), also returned for unknown or deleted handles.
     InvalidArg,
+    /// Runtime failure in libdatachannel (`RTC_ERR_FAILURE`).
     Runtime,
+    /// Element not available (`RTC_ERR_NOT_AVAIL`).
     NotAvailable,
+    /// Buffer too small (`RTC_ERR_TOO_SMALL`).
     TooSmall,
-    Unkown,
+    /// A libdatachannel error code this crate doesn't know about.
+    Unknown(i32),
+    /// A message or buffer is larger than what can be handled.
+    TooLarge(usize),
+    /// The send buffer of a data channel is full, see `DataChannelHandle::try_send`.
+    BufferFull {
+        buffered: usize,
+    },
+    /// A string couldn't be passed to or read from libdatachannel, e.g. nul or invalid UTF-8.
     BadString(String),
+    /// A session description, or its type, couldn't be parsed.
+    BadSdp(String),
+    /// A remote description was rejected by `RtcPeerConnection::verify_remote_fingerprint`.
+    FingerprintMismatch,
+    /// The functionality needs a cargo feature this build was made without.
+    FeatureDisabled(&'static str),
+    /// An operation didn't complete in time.
+    Timeout,
+    /// A remote description doesn't comply with the `FingerprintPolicy` of the connection.
+    PolicyViolation(String),
+    /// A signaling server couldn't be reached, or failed.
+    Signaling(String),
+    /// Global resources can't be released while connections, data channels or tracks
+    /// (as many as carried) are alive.
+    StillInUse(usize),
+    /// The data channel was closed.
+    Closed,
+    /// Sending several messages failed with `error` after the first `sent` ones.
+    PartiallySent {
+        sent: usize,
+        error: Box<Error>,
+    },
 }
 
 impl From<i32> for Error {
     fn from(code: i32) -> Self {
         match code {
-            -1 => Self::InvalidArg,
-            -2 => Self::Runtime,
-            -3 => Self::NotAvailable,
-            -4 => Self::TooSmall,
-            _ => Self::Unkown,
+            sys::RTC_ERR_INVALID => Self::InvalidArg,
+            sys::RTC_ERR_FAILURE => Self::Runtime,
+            sys::RTC_ERR_NOT_AVAIL => Self::NotAvailable,
+            sys::RTC_ERR_TOO_SMALL => Self::TooSmall,
+            _ => Self::Unknown(code),
         }
     }
 }
@@ -39,8 +77,21 @@ impl Display for Error {
             Self::Runtime => write!(f, "RuntimeError"),
             Self::NotAvailable => write!(f, "NotAvailable"),
             Self::TooSmall => write!(f, "TooSmall"),
-            Self::Unkown => write!(f, "UnknownError"),
+            Self::Unknown(code) => write!(f, "UnknownError: {}", code),
+            Self::TooLarge(size) => write!(f, "TooLarge: {} bytes", size),
+            Self::BufferFull { buffered } => write!(f, "BufferFull: {} bytes buffered", buffered),
             Self::BadString(msg) => write!(f, "BadString: {}", msg),
+            Self::BadSdp(msg) => write!(f, "BadSdp: {}", msg),
+            Self::FingerprintMismatch => write!(f, "FingerprintMismatch"),
+            Self::FeatureDisabled(feature) => write!(f, "FeatureDisabled: {}", feature),
+            Self::Timeout => write!(f, "Timeout"),
+            Self::PolicyViolation(msg) => write!(f, "PolicyViolation: {}", msg),
+            Self::Signaling(msg) => write!(f, "SignalingError: {}", msg),
+            Self::StillInUse(alive) => write!(f, "StillInUse: {} objects alive", alive),
+            Self::Closed => write!(f, "Closed"),
+            Self::PartiallySent { sent, error } => {
+                write!(f, "PartiallySent: {} messages sent, then {}", sent, error)
+            }
         }
     }
 }
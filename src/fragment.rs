@@ -0,0 +1,151 @@
+use std::convert::{TryFrom, TryInto};
+
+use crate::datachannel::{DataChannelHandler, Message};
+use crate::error::{Error, Result};
+use crate::wrapper::{deliver_held, forward_handler, wrapper_accessors};
+
+/// Size of the big endian `u32` length prefixing the first fragment of a message.
+const HEADER_SIZE: usize = 4;
+
+/// Splits `msg` into fragments of at most `max_size` bytes, to be reassembled by a
+/// [`Fragmented`] handler.
+///
+/// The first fragment starts with the length of `msg`, the following ones carry the rest
+/// of it, so even small messages are prefixed with a header.
+pub fn fragment(msg: &[u8], max_size: usize) -> Result<Vec<Vec<u8>>> {
+    if max_size <= HEADER_SIZE {
+        return Err(Error::InvalidArg);
+    }
+    let len = u32::try_from(msg.len()).map_err(|_| Error::TooLarge(msg.len()))?;
+
+    let first_size = msg.len().min(max_size - HEADER_SIZE);
+    let mut first = Vec::with_capacity(HEADER_SIZE + first_size);
+    first.extend_from_slice(&len.to_be_bytes());
+    first.extend_from_slice(&msg[..first_size]);
+
+    let mut fragments = vec![first];
+    fragments.extend(
+        msg[first_size..]
+            .chunks(max_size)
+            .map(|chunk| chunk.to_vec()),
+    );
+    Ok(fragments)
+}
+
+enum State {
+    Idle,
+    Reassembling {
+        len: usize,
+        buf: Vec<u8>,
+    },
+    /// Skipping the fragments of a message above the maximum size.
+    Discarding {
+        remaining: usize,
+    },
+}
+
+/// A [`DataChannelHandler`] wrapper reassembling the messages split by [`fragment`],
+/// e.g. sent with `RtcDataChannel::send_fragmented`.
+///
+/// Fragments must arrive in order, so the data channel has to be reliable and ordered.
+/// Reassembled messages are delivered as binary, and messages above the maximum size
+/// (64 MiB by default) are discarded.
+pub struct Fragmented<D> {
+    inner: D,
+    max_size: usize,
+    state: State,
+}
+
+impl<D> Fragmented<D>
+where
+    D: DataChannelHandler,
+{
+    pub fn new(inner: D) -> Self {
+        Fragmented {
+            inner,
+            max_size: 64 * 1024 * 1024,
+            state: State::Idle,
+        }
+    }
+
+    pub fn max_size(mut self, max_size: usize) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    /// Adds a fragment, returning the message it completes, if any.
+    fn reassemble(&mut self, fragment: &[u8]) -> Option<Vec<u8>> {
+        match &mut self.state {
+            State::Idle => {
+                if fragment.len() < HEADER_SIZE {
+                    log::warn!("Ignoring fragment without header");
+                    return None;
+                }
+                let len = u32::from_be_bytes(fragment[..HEADER_SIZE].try_into().unwrap()) as usize;
+                let data = &fragment[HEADER_SIZE..];
+                if len > self.max_size {
+                    log::warn!("Discarding message of {} bytes, above maximum size", len);
+                    self.state = State::Discarding { remaining: len };
+                    return self.reassemble(data);
+                }
+                // Grown as fragments arrive, the length comes from the peer
+                self.state = State::Reassembling {
+                    len,
+                    buf: Vec::new(),
+                };
+                self.reassemble(data)
+            }
+            State::Reassembling { len, buf } => {
+                if buf.len() + fragment.len() > *len {
+                    // Skips the rest of the message rather than parsing it as headers
+                    log::warn!("Discarding message with fragments above its length");
+                    let remaining = *len - buf.len();
+                    self.state = State::Discarding { remaining };
+                    return None;
+                }
+                buf.extend_from_slice(fragment);
+                if buf.len() < *len {
+                    return None;
+                }
+                match std::mem::replace(&mut self.state, State::Idle) {
+                    State::Reassembling { buf, .. } => Some(buf),
+                    _ => unreachable!(),
+                }
+            }
+            State::Discarding { remaining } => {
+                *remaining = remaining.saturating_sub(fragment.len());
+                if *remaining == 0 {
+                    self.state = State::Idle;
+                }
+                None
+            }
+        }
+    }
+}
+
+wrapper_accessors!(Fragmented);
+
+impl<D> DataChannelHandler for Fragmented<D>
+where
+    D: DataChannelHandler,
+{
+    forward_handler!(
+        on_created,
+        on_open,
+        on_closed,
+        on_error,
+        on_message,
+        on_buffered_amount_low,
+        on_available,
+        on_quota_exceeded,
+        owned_messages,
+        on_message_owned,
+        subscriptions,
+    );
+
+    fn on_typed_message(&mut self, msg: Message) {
+        if let Some(msg) = self.reassemble(msg.as_bytes()) {
+            deliver_held(&mut self.inner, msg, false)
+        }
+    }
+}
@@ -0,0 +1,49 @@
+/// A `tracing` span, which is nothing when built without the `tracing` feature.
+#[derive(Debug, Clone)]
+pub(crate) struct Span {
+    #[cfg(feature = "tracing")]
+    inner: tracing::Span,
+}
+
+/// Guard returned by [`Span::entered`].
+pub(crate) struct Entered {
+    #[cfg(feature = "tracing")]
+    _inner: tracing::span::EnteredSpan,
+}
+
+impl Span {
+    #[allow(unused_variables)]
+    pub(crate) fn connection(pc_id: i32) -> Self {
+        Span {
+            #[cfg(feature = "tracing")]
+            inner: tracing::info_span!("rtc_peer_connection", pc_id),
+        }
+    }
+
+    #[allow(unused_variables)]
+    pub(crate) fn channel(parent: &Span, dc_id: i32) -> Self {
+        Span {
+            #[cfg(feature = "tracing")]
+            inner: tracing::info_span!(parent: &parent.inner, "rtc_data_channel", dc_id),
+        }
+    }
+
+    /// Enters the span, so that events of this crate and of the handlers are recorded
+    /// within it.
+    pub(crate) fn entered(&self) -> Entered {
+        Entered {
+            #[cfg(feature = "tracing")]
+            _inner: self.inner.clone().entered(),
+        }
+    }
+}
+
+/// Emits a `tracing` event, expands to nothing without the `tracing` feature.
+macro_rules! event {
+    ($level:ident, $($arg:tt)+) => {
+        #[cfg(feature = "tracing")]
+        tracing::event!(tracing::Level::$level, $($arg)+);
+    };
+}
+
+pub(crate) use event;
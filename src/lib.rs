@@ -1,68 +1,150 @@
+#[cfg(feature = "async")]
+mod broadcast;
+mod callbacks;
+mod capabilities;
+mod chunk;
 mod config;
+#[cfg(feature = "correlation")]
+mod correlation;
 mod datachannel;
+mod diff;
+mod dispatch;
 mod error;
+mod fragment;
+mod instrument;
+mod logging;
+mod manager;
+mod media;
+#[cfg(feature = "test-util")]
+mod mock;
 mod peerconnection;
+mod policy;
+mod probe;
+mod queue;
+mod quota;
+mod registry;
+mod reorder;
+mod signaling;
+mod timer;
+#[cfg(feature = "tokio")]
+mod tokio_channel;
+mod track;
+#[cfg(feature = "websocket")]
+mod websocket;
+mod wrapper;
 
-mod sys {
-    use std::ffi::CStr;
-    use std::os::raw::c_char;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
-    use datachannel_sys as sys;
-    use lazy_static::lazy_static;
+fn ffi_string(ffi: &[u8]) -> crate::error::Result<String> {
+    use std::ffi::CStr;
+    let bytes = CStr::to_bytes(CStr::from_bytes_with_nul(&ffi)?);
+    Ok(lossy_string(bytes))
+}
 
-    lazy_static! {
-        pub(super) static ref INIT_LOGGING: () = {
-            let level = match log::max_level() {
-                log::LevelFilter::Off => sys::rtcLogLevel_RTC_LOG_NONE,
-                log::LevelFilter::Error => sys::rtcLogLevel_RTC_LOG_ERROR,
-                log::LevelFilter::Warn => sys::rtcLogLevel_RTC_LOG_WARNING,
-                log::LevelFilter::Info => sys::rtcLogLevel_RTC_LOG_INFO,
-                log::LevelFilter::Debug => sys::rtcLogLevel_RTC_LOG_DEBUG,
-                log::LevelFilter::Trace => sys::rtcLogLevel_RTC_LOG_VERBOSE,
-            };
-            unsafe { sys::rtcInitLogger(level, Some(log_callback)) };
-        };
+/// Converts a nul terminated string passed by libdatachannel, see [`lossy_string`].
+unsafe fn ffi_str(ptr: *const std::os::raw::c_char) -> String {
+    if ptr.is_null() {
+        return String::new();
     }
+    lossy_string(std::ffi::CStr::from_ptr(ptr).to_bytes())
+}
 
-    unsafe extern "C" fn log_callback(level: sys::rtcLogLevel, message: *const c_char) {
-        let message = CStr::from_ptr(message).to_string_lossy();
-        match level {
-            sys::rtcLogLevel_RTC_LOG_NONE => (),
-            sys::rtcLogLevel_RTC_LOG_ERROR => log::error!("{}", message),
-            sys::rtcLogLevel_RTC_LOG_WARNING => log::warn!("{}", message),
-            sys::rtcLogLevel_RTC_LOG_INFO => log::info!("{}", message),
-            sys::rtcLogLevel_RTC_LOG_DEBUG => log::debug!("{}", message),
-            sys::rtcLogLevel_RTC_LOG_VERBOSE => log::trace!("{}", message),
-            _ => unreachable!(),
+/// Strings from libdatachannel may carry invalid UTF-8 sent by a misbehaving peer (e.g.
+/// in candidates, descriptions or channel labels), it is replaced by U+FFFD rather than
+/// failing or panicking in a callback, and a warning is logged.
+fn lossy_string(bytes: &[u8]) -> String {
+    use std::borrow::Cow;
+    match String::from_utf8_lossy(bytes) {
+        Cow::Borrowed(s) => s.to_string(),
+        Cow::Owned(s) => {
+            log::warn!("Replaced invalid UTF-8 in string from libdatachannel");
+            log::debug!("{}", s);
+            s
         }
     }
 }
 
-fn ensure_logging() {
-    *sys::INIT_LOGGING;
+/// Connections, data channels and tracks alive, which [`cleanup`] would pull from under
+/// their owners.
+static LIVE_OBJECTS: AtomicUsize = AtomicUsize::new(0);
+
+fn object_created() {
+    LIVE_OBJECTS.fetch_add(1, Ordering::SeqCst);
 }
 
-fn ffi_string(ffi: &[u8]) -> crate::error::Result<String> {
-    use std::ffi::CStr;
-    let bytes = CStr::to_bytes(CStr::from_bytes_with_nul(&ffi)?);
-    Ok(String::from_utf8(bytes.to_vec())?)
+fn object_dropped() {
+    LIVE_OBJECTS.fetch_sub(1, Ordering::SeqCst);
 }
 
-/// An optional function to preload resources, otherwise they will be loaded lazily.
+/// Initializes the global resources of libdatachannel (e.g. its thread pool and TLS
+/// context), which are otherwise initialized when creating the first connection.
+///
+/// Calling it at startup moves that cost out of the first connection.
 pub fn preload() {
     unsafe { datachannel_sys::rtcPreload() };
 }
 
-/// An optional resources cleanup function.
-pub fn cleanup() {
-    unsafe { datachannel_sys::rtcCleanup() };
+/// Releases the global resources of libdatachannel, joining its background threads, e.g.
+/// at shutdown so that leak checkers don't report them.
+///
+/// All connections, data channels and tracks must have been dropped, otherwise
+/// [`Error::StillInUse`] is returned with how many are alive and nothing is released.
+/// Resources are initialized again when needed afterwards. This blocks the calling
+/// thread, so it must not be called from a handler.
+///
+/// [`Error::StillInUse`]: crate::Error::StillInUse
+pub fn cleanup() -> Result<()> {
+    match LIVE_OBJECTS.load(Ordering::SeqCst) {
+        0 => {
+            unsafe { datachannel_sys::rtcCleanup() };
+            Ok(())
+        }
+        alive => Err(Error::StillInUse(alive)),
+    }
 }
 
-pub use crate::config::{CertificateType, RtcConfig};
-pub use crate::datachannel::{DataChannelHandler, DataChannelInit, Reliability, RtcDataChannel};
+#[cfg(feature = "async")]
+pub use crate::broadcast::{BroadcastHandler, ConnectionEvent, EventBroadcaster};
+pub use crate::callbacks::{DataChannelCallbacks, PeerConnectionCallbacks};
+pub use crate::capabilities::{capabilities, version, Capabilities, CryptoBackend};
+pub use crate::chunk::{ChunkConfig, ChunkSizer};
+pub use crate::config::{AddressFamily, CertificateType, RtcConfig, SharedConfig};
+#[cfg(feature = "correlation")]
+pub use crate::correlation::{frame_correlated, Correlated, CorrelationId};
+pub use crate::datachannel::{
+    DataChannelHandle, DataChannelHandler, DataChannelInit, Message, Reliability, RtcDataChannel,
+    Subscriptions,
+};
+pub use crate::diff::{DirectionChange, MediaDirection, MediaSection, SdpDiff};
+pub use crate::dispatch::{Dispatched, Dispatcher, Task};
+pub use crate::error::{Error, Result};
+pub use crate::fragment::{fragment, Fragmented};
+pub use crate::logging::{configure_logging, LogLevel};
+pub use crate::manager::{ConnectionManager, Managed};
+pub use crate::media::{MediaDescription, MediaKind};
+#[cfg(feature = "test-util")]
+pub use crate::mock::{
+    connected_pair, DataChannelCall, MockDataChannel, MockPeerConnection, PeerConnectionCall,
+    Recorder,
+};
 pub use crate::peerconnection::{
-    fmt_sdp, serde_sdp, CandidatePair, ConnectionState, GatheringState, IceCandidate,
-    PeerConnectionHandler, RtcPeerConnection, SdpType, SessionDescription,
+    fmt_sdp, serde_sdp, CandidatePair, ConnectionState, FailureReason, Fingerprint, GatheringState,
+    IceCandidate, PeerConnectionHandle, PeerConnectionHandler, RtcPeerConnection, SdpType,
+    SessionDescription, SignalingState,
+};
+pub use crate::policy::FingerprintPolicy;
+pub use crate::probe::{ProbeSummary, Probed, Prober};
+pub use crate::queue::{ChannelKey, Event, EventQueue, QueuedChannel};
+pub use crate::quota::{Quota, QuotaAction, QuotaScope};
+pub use crate::registry::{ChannelInfo, ConnectionInfo, Registry};
+pub use crate::reorder::{ReorderBuffer, ReorderConfig, Sequencer};
+pub use crate::signaling::{Signal, Signaling};
+#[cfg(feature = "tokio")]
+pub use crate::tokio_channel::{
+    TokioChannelHandler, TokioDataChannel, TokioEvent, TokioPeerConnectionHandler,
 };
+pub use crate::track::{media_supported, Packetizer, RtcTrack, RtpConfig, TrackHandler};
+#[cfg(feature = "websocket")]
+pub use crate::websocket::{WebSocketSender, WebSocketSignaling};
 
 pub use webrtc_sdp as sdp;
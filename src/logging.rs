@@ -0,0 +1,73 @@
+use std::os::raw::c_char;
+
+use datachannel_sys as sys;
+use lazy_static::lazy_static;
+
+lazy_static! {
+    static ref INIT_LOGGING: () = {
+        let level = match log::max_level() {
+            log::LevelFilter::Off => LogLevel::None,
+            log::LevelFilter::Error => LogLevel::Error,
+            log::LevelFilter::Warn => LogLevel::Warning,
+            log::LevelFilter::Info => LogLevel::Info,
+            log::LevelFilter::Debug => LogLevel::Debug,
+            log::LevelFilter::Trace => LogLevel::Verbose,
+        };
+        unsafe { sys::rtcInitLogger(level.as_raw(), Some(log_callback)) };
+    };
+}
+
+/// Verbosity of libdatachannel logs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LogLevel {
+    None,
+    Fatal,
+    Error,
+    Warning,
+    Info,
+    Debug,
+    Verbose,
+}
+
+impl LogLevel {
+    fn as_raw(&self) -> sys::rtcLogLevel {
+        match self {
+            Self::None => sys::rtcLogLevel_RTC_LOG_NONE,
+            Self::Fatal => sys::rtcLogLevel_RTC_LOG_FATAL,
+            Self::Error => sys::rtcLogLevel_RTC_LOG_ERROR,
+            Self::Warning => sys::rtcLogLevel_RTC_LOG_WARNING,
+            Self::Info => sys::rtcLogLevel_RTC_LOG_INFO,
+            Self::Debug => sys::rtcLogLevel_RTC_LOG_DEBUG,
+            Self::Verbose => sys::rtcLogLevel_RTC_LOG_VERBOSE,
+        }
+    }
+}
+
+/// Sets the level of libdatachannel logs, which are forwarded to the `log` crate
+/// (`Fatal` and `Verbose` records are logged as errors and traces respectively).
+///
+/// By default the level follows `log::max_level()` when the first connection is
+/// created, a `Verbose` level is typically needed to debug ICE failures. This can be
+/// called at any time, as well as before or after creating connections.
+pub fn configure_logging(level: LogLevel) {
+    ensure_logging();
+    unsafe { sys::rtcInitLogger(level.as_raw(), Some(log_callback)) };
+}
+
+pub(crate) fn ensure_logging() {
+    *INIT_LOGGING;
+}
+
+unsafe extern "C" fn log_callback(level: sys::rtcLogLevel, message: *const c_char) {
+    let message = crate::ffi_str(message);
+    match level {
+        sys::rtcLogLevel_RTC_LOG_NONE => (),
+        sys::rtcLogLevel_RTC_LOG_FATAL => log::error!("{}", message),
+        sys::rtcLogLevel_RTC_LOG_ERROR => log::error!("{}", message),
+        sys::rtcLogLevel_RTC_LOG_WARNING => log::warn!("{}", message),
+        sys::rtcLogLevel_RTC_LOG_INFO => log::info!("{}", message),
+        sys::rtcLogLevel_RTC_LOG_DEBUG => log::debug!("{}", message),
+        sys::rtcLogLevel_RTC_LOG_VERBOSE => log::trace!("{}", message),
+        _ => log::info!("{}", message),
+    }
+}
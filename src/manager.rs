@@ -0,0 +1,301 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+
+use crate::config::SharedConfig;
+use crate::datachannel::{DataChannelHandle, DataChannelHandler, DataChannelInit, RtcDataChannel};
+use crate::diff::SdpDiff;
+use crate::error::{Error, Result};
+use crate::peerconnection::{
+    CandidatePair, ConnectionState, FailureReason, GatheringState, IceCandidate,
+    PeerConnectionHandler, RtcPeerConnection, SdpType, SessionDescription, SignalingState,
+};
+
+struct ManagedChannel {
+    label: String,
+    handle: DataChannelHandle,
+}
+
+struct State<K> {
+    channels: HashMap<K, Vec<ManagedChannel>>,
+    /// Peers whose connection failed or closed, removed by the next cleanup.
+    ended: Vec<K>,
+}
+
+impl<K> Default for State<K> {
+    fn default() -> Self {
+        State {
+            channels: HashMap::new(),
+            ended: Vec::new(),
+        }
+    }
+}
+
+/// The handler of the connections of a [`ConnectionManager`], which wraps the ones built
+/// by its factory.
+pub struct Managed<K, P> {
+    peer: K,
+    inner: P,
+    state: Arc<Mutex<State<K>>>,
+}
+
+impl<K, P> Managed<K, P> {
+    pub fn peer(&self) -> &K {
+        &self.peer
+    }
+
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    pub fn inner_mut(&mut self) -> &mut P {
+        &mut self.inner
+    }
+}
+
+impl<K, P> PeerConnectionHandler for Managed<K, P>
+where
+    K: Clone + Eq + Hash,
+    P: PeerConnectionHandler,
+    P::DCH: DataChannelHandler + Send,
+{
+    type DCH = P::DCH;
+
+    fn data_channel_handler(&mut self) -> Self::DCH {
+        self.inner.data_channel_handler()
+    }
+
+    fn on_description(&mut self, sess_desc: SessionDescription) {
+        self.inner.on_description(sess_desc)
+    }
+
+    fn on_description_diff(&mut self, diff: SdpDiff) {
+        self.inner.on_description_diff(diff)
+    }
+
+    fn on_candidate(&mut self, cand: IceCandidate) {
+        self.inner.on_candidate(cand)
+    }
+
+    fn on_connection_state_change(&mut self, state: ConnectionState) {
+        if let ConnectionState::Failed | ConnectionState::Closed = state {
+            // The connection can't be dropped from its own callback, its channels can
+            let mut state = self.state.lock();
+            state.channels.remove(&self.peer);
+            state.ended.push(self.peer.clone());
+        }
+        self.inner.on_connection_state_change(state)
+    }
+
+    fn on_gathering_state_change(&mut self, state: GatheringState) {
+        self.inner.on_gathering_state_change(state)
+    }
+
+    fn on_signaling_state_change(&mut self, state: SignalingState) {
+        self.inner.on_signaling_state_change(state)
+    }
+
+    fn on_candidate_pair_change(&mut self, old: Option<CandidatePair>, new: CandidatePair) {
+        self.inner.on_candidate_pair_change(old, new)
+    }
+
+    fn on_failure(&mut self, reason: FailureReason) {
+        self.inner.on_failure(reason)
+    }
+
+    fn on_data_channel(&mut self, data_channel: Box<RtcDataChannel<Self::DCH>>) {
+        let channel = ManagedChannel {
+            label: data_channel.label(),
+            handle: data_channel.handle(),
+        };
+        self.state
+            .lock()
+            .channels
+            .entry(self.peer.clone())
+            .or_default()
+            .push(channel);
+        self.inner.on_data_channel(data_channel)
+    }
+}
+
+type HandlerFactory<K, P> = Box<dyn FnMut(&K) -> P + Send>;
+
+/// Owns the connections to many peers keyed by their id, e.g. for mesh rooms.
+///
+/// Connections are created by [`connect`] or when receiving an offer from a new peer,
+/// with the current configuration and a handler built by the factory, which is given the
+/// id of the peer (typically to address the descriptions and candidates it signals).
+/// Failed and closed connections are dropped by the next call to [`cleanup`], or to any
+/// method adding or routing to peers, and their data channels are forgotten as soon as
+/// they fail or close.
+///
+/// Data channels are returned to their creator, or passed to the `on_data_channel` of
+/// their connection handler, and must be kept as usual. The manager keeps track of their
+/// handles for [`broadcast`].
+///
+/// [`connect`]: ConnectionManager::connect
+/// [`cleanup`]: ConnectionManager::cleanup
+/// [`broadcast`]: ConnectionManager::broadcast
+pub struct ConnectionManager<K, P> {
+    config: SharedConfig,
+    factory: HandlerFactory<K, P>,
+    peers: HashMap<K, Box<RtcPeerConnection<Managed<K, P>>>>,
+    state: Arc<Mutex<State<K>>>,
+}
+
+impl<K, P> ConnectionManager<K, P>
+where
+    K: Clone + Eq + Hash + Send + 'static,
+    P: PeerConnectionHandler + Send + 'static,
+    P::DCH: DataChannelHandler + Send,
+{
+    pub fn new<F>(config: SharedConfig, factory: F) -> Self
+    where
+        F: FnMut(&K) -> P + Send + 'static,
+    {
+        ConnectionManager {
+            config,
+            factory: Box::new(factory),
+            peers: HashMap::new(),
+            state: Arc::new(Mutex::new(State::default())),
+        }
+    }
+
+    /// Returns the connection to `peer`, creating it if needed.
+    pub fn connect(&mut self, peer: K) -> Result<&mut RtcPeerConnection<Managed<K, P>>> {
+        self.cleanup();
+        if !self.peers.contains_key(&peer) {
+            let handler = Managed {
+                peer: peer.clone(),
+                inner: (self.factory)(&peer),
+                state: self.state.clone(),
+            };
+            let pc = RtcPeerConnection::new(&self.config.get(), handler)?;
+            self.peers.insert(peer.clone(), pc);
+        }
+        Ok(self.peers.get_mut(&peer).unwrap())
+    }
+
+    pub fn get(&self, peer: &K) -> Option<&RtcPeerConnection<Managed<K, P>>> {
+        self.peers.get(peer).map(|pc| &**pc)
+    }
+
+    pub fn get_mut(&mut self, peer: &K) -> Option<&mut RtcPeerConnection<Managed<K, P>>> {
+        self.peers.get_mut(peer).map(|pc| &mut **pc)
+    }
+
+    pub fn peers(&self) -> impl Iterator<Item = &K> {
+        self.peers.keys()
+    }
+
+    /// The number of data channels tracked for [`broadcast`], those of failed and closed
+    /// connections are forgotten right away.
+    ///
+    /// [`broadcast`]: ConnectionManager::broadcast
+    pub fn channel_count(&self) -> usize {
+        self.state.lock().channels.values().map(Vec::len).sum()
+    }
+
+    /// Removes the connection to `peer`, which is closed once the returned value is
+    /// dropped.
+    pub fn remove(&mut self, peer: &K) -> Option<Box<RtcPeerConnection<Managed<K, P>>>> {
+        self.state.lock().channels.remove(peer);
+        self.peers.remove(peer)
+    }
+
+    /// Drops the failed and closed connections, returning the ids of their peers.
+    pub fn cleanup(&mut self) -> Vec<K> {
+        let ended = std::mem::take(&mut self.state.lock().ended);
+        ended
+            .into_iter()
+            .filter(|peer| self.remove(peer).is_some())
+            .collect()
+    }
+
+    /// Routes a description received from `peer`, offers from new peers create their
+    /// connection.
+    pub fn set_remote_description(
+        &mut self,
+        peer: K,
+        sess_desc: &SessionDescription,
+    ) -> Result<()> {
+        self.cleanup();
+        let pc = match sess_desc.sdp_type {
+            SdpType::Offer => self.connect(peer)?,
+            _ => self.peers.get_mut(&peer).ok_or(Error::InvalidArg)?,
+        };
+        pc.set_remote_description(sess_desc)
+    }
+
+    /// Routes a candidate received from `peer`, fails with [`Error::InvalidArg`] for
+    /// unknown peers.
+    pub fn add_remote_candidate(&mut self, peer: &K, cand: &IceCandidate) -> Result<()> {
+        self.cleanup();
+        match self.peers.get_mut(peer) {
+            Some(pc) => pc.add_remote_candidate(cand),
+            None => Err(Error::InvalidArg),
+        }
+    }
+
+    /// Creates a data channel to `peer`, which is tracked for [`broadcast`].
+    ///
+    /// [`broadcast`]: ConnectionManager::broadcast
+    pub fn create_data_channel(
+        &mut self,
+        peer: &K,
+        label: &str,
+        dc_handler: P::DCH,
+        dc_init: &DataChannelInit,
+    ) -> Result<Box<RtcDataChannel<P::DCH>>> {
+        let pc = self.peers.get_mut(peer).ok_or(Error::InvalidArg)?;
+        let dc = pc.create_data_channel_ex(label, dc_handler, dc_init)?;
+        let channel = ManagedChannel {
+            label: label.to_string(),
+            handle: dc.handle(),
+        };
+        self.state
+            .lock()
+            .channels
+            .entry(peer.clone())
+            .or_default()
+            .push(channel);
+        Ok(dc)
+    }
+
+    /// Sends `msg` on the data channels labeled `label` of all the peers, returning how
+    /// many sent it. Channels that are not open yet are skipped, deleted ones are
+    /// forgotten.
+    pub fn broadcast(&mut self, label: &str, msg: &[u8]) -> usize {
+        self.cleanup();
+        // Not sent under the lock, which handlers take from libdatachannel threads
+        let handles = self
+            .state
+            .lock()
+            .channels
+            .values()
+            .flatten()
+            .filter(|channel| channel.label == label)
+            .map(|channel| channel.handle.clone())
+            .collect::<Vec<_>>();
+
+        let mut sent = 0;
+        let mut deleted = Vec::new();
+        for handle in handles {
+            match handle.send(msg) {
+                Ok(()) => sent += 1,
+                // Deleted data channel
+                Err(Error::InvalidArg) => deleted.push(handle.id()),
+                Err(_) => (),
+            }
+        }
+
+        if !deleted.is_empty() {
+            for channels in self.state.lock().channels.values_mut() {
+                channels.retain(|channel| !deleted.contains(&channel.handle.id()));
+            }
+        }
+        sent
+    }
+}
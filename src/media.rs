@@ -0,0 +1,213 @@
+use std::fmt::{self, Display};
+
+use crate::diff::MediaDirection;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MediaKind {
+    Audio,
+    Video,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct PayloadType {
+    id: u8,
+    codec: String,
+    clock_rate: u32,
+    channels: Option<u8>,
+    fmtp: Vec<String>,
+    rtcp_fbs: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Ssrc {
+    ssrc: u32,
+    cname: String,
+}
+
+/// Builds the SDP media section of an audio or video track, as taken by
+/// `RtcPeerConnection::add_track`.
+///
+/// ```ignore
+/// let media = MediaDescription::video("video")
+///     .direction(MediaDirection::SendOnly)
+///     .payload_type(96, "H264")
+///     .fmtp(96, "profile-level-id=42e01f;packetization-mode=1")
+///     .ssrc(42, "stream");
+/// let track = pc.add_track(&media.to_string(), handler)?;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct MediaDescription {
+    kind: MediaKind,
+    mid: String,
+    direction: MediaDirection,
+    payload_types: Vec<PayloadType>,
+    ssrcs: Vec<Ssrc>,
+    msid: Option<(String, String)>,
+    bitrate: Option<u32>,
+}
+
+impl MediaDescription {
+    pub fn audio(mid: &str) -> Self {
+        Self::new(MediaKind::Audio, mid)
+    }
+
+    pub fn video(mid: &str) -> Self {
+        Self::new(MediaKind::Video, mid)
+    }
+
+    fn new(kind: MediaKind, mid: &str) -> Self {
+        MediaDescription {
+            kind,
+            mid: mid.to_string(),
+            direction: MediaDirection::SendRecv,
+            payload_types: Vec::new(),
+            ssrcs: Vec::new(),
+            msid: None,
+            bitrate: None,
+        }
+    }
+
+    pub fn kind(&self) -> MediaKind {
+        self.kind
+    }
+
+    pub fn mid(&self) -> &str {
+        &self.mid
+    }
+
+    /// `SendRecv` by default.
+    pub fn direction(mut self, direction: MediaDirection) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    /// Adds a payload type with the usual clock rate of `codec`: 90 kHz for video, 48 kHz
+    /// stereo for Opus, and 8 kHz for other audio codecs.
+    pub fn payload_type(self, id: u8, codec: &str) -> Self {
+        let (clock_rate, channels) = match self.kind {
+            MediaKind::Video => (90_000, None),
+            MediaKind::Audio if codec.eq_ignore_ascii_case("opus") => (48_000, Some(2)),
+            MediaKind::Audio => (8_000, None),
+        };
+        self.payload_type_ex(id, codec, clock_rate, channels)
+    }
+
+    /// Adds a payload type with an explicit clock rate and number of audio channels.
+    pub fn payload_type_ex(
+        mut self,
+        id: u8,
+        codec: &str,
+        clock_rate: u32,
+        channels: Option<u8>,
+    ) -> Self {
+        self.payload_types.push(PayloadType {
+            id,
+            codec: codec.to_string(),
+            clock_rate,
+            channels,
+            fmtp: Vec::new(),
+            rtcp_fbs: Vec::new(),
+        });
+        self
+    }
+
+    /// Adds format parameters (`a=fmtp`) to payload type `id`, which must have been added.
+    pub fn fmtp(mut self, id: u8, params: &str) -> Self {
+        if let Some(payload_type) = self.payload_type_mut(id) {
+            payload_type.fmtp.push(params.to_string());
+        }
+        self
+    }
+
+    /// Adds an RTCP feedback (`a=rtcp-fb`, e.g. `nack` or `nack pli`) to payload type
+    /// `id`, which must have been added.
+    pub fn rtcp_fb(mut self, id: u8, feedback: &str) -> Self {
+        if let Some(payload_type) = self.payload_type_mut(id) {
+            payload_type.rtcp_fbs.push(feedback.to_string());
+        }
+        self
+    }
+
+    /// Adds a source sent on the track, identified by its `cname`.
+    pub fn ssrc(mut self, ssrc: u32, cname: &str) -> Self {
+        self.ssrcs.push(Ssrc {
+            ssrc,
+            cname: cname.to_string(),
+        });
+        self
+    }
+
+    /// Sets the media stream and track ids (`a=msid`), which browsers use to group
+    /// tracks, e.g. for lip sync.
+    pub fn msid(mut self, stream_id: &str, track_id: &str) -> Self {
+        self.msid = Some((stream_id.to_string(), track_id.to_string()));
+        self
+    }
+
+    /// Sets the maximum bitrate (`b=AS`), in kbps.
+    pub fn bitrate(mut self, kbps: u32) -> Self {
+        self.bitrate = Some(kbps);
+        self
+    }
+
+    fn payload_type_mut(&mut self, id: u8) -> Option<&mut PayloadType> {
+        let payload_type = self.payload_types.iter_mut().find(|pt| pt.id == id);
+        if payload_type.is_none() {
+            log::warn!("Unknown payload type {} in MediaDescription", id);
+        }
+        payload_type
+    }
+}
+
+impl Display for MediaDescription {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = match self.kind {
+            MediaKind::Audio => "audio",
+            MediaKind::Video => "video",
+        };
+        write!(f, "m={} 9 UDP/TLS/RTP/SAVPF", kind)?;
+        for payload_type in self.payload_types.iter() {
+            write!(f, " {}", payload_type.id)?;
+        }
+        write!(f, "\r\n")?;
+        if let Some(kbps) = self.bitrate {
+            write!(f, "b=AS:{}\r\n", kbps)?;
+        }
+        write!(f, "a=mid:{}\r\n", self.mid)?;
+        let direction = match self.direction {
+            MediaDirection::SendRecv => "sendrecv",
+            MediaDirection::SendOnly => "sendonly",
+            MediaDirection::RecvOnly => "recvonly",
+            MediaDirection::Inactive => "inactive",
+        };
+        write!(f, "a={}\r\n", direction)?;
+        write!(f, "a=rtcp-mux\r\n")?;
+        for pt in self.payload_types.iter() {
+            write!(f, "a=rtpmap:{} {}/{}", pt.id, pt.codec, pt.clock_rate)?;
+            if let Some(channels) = pt.channels {
+                write!(f, "/{}", channels)?;
+            }
+            write!(f, "\r\n")?;
+            for feedback in pt.rtcp_fbs.iter() {
+                write!(f, "a=rtcp-fb:{} {}\r\n", pt.id, feedback)?;
+            }
+            for params in pt.fmtp.iter() {
+                write!(f, "a=fmtp:{} {}\r\n", pt.id, params)?;
+            }
+        }
+        if let Some((stream_id, track_id)) = &self.msid {
+            write!(f, "a=msid:{} {}\r\n", stream_id, track_id)?;
+        }
+        for ssrc in self.ssrcs.iter() {
+            write!(f, "a=ssrc:{} cname:{}\r\n", ssrc.ssrc, ssrc.cname)?;
+            if let Some((stream_id, track_id)) = &self.msid {
+                write!(
+                    f,
+                    "a=ssrc:{} msid:{} {}\r\n",
+                    ssrc.ssrc, stream_id, track_id
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
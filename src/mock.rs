@@ -0,0 +1,347 @@
+use std::sync::mpsc::{self, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use parking_lot::{Condvar, Mutex};
+
+use crate::config::RtcConfig;
+use crate::datachannel::{
+    DataChannelHandle, DataChannelHandler, Message, RtcDataChannel, Subscriptions,
+};
+use crate::diff::SdpDiff;
+use crate::error::Result;
+use crate::peerconnection::{
+    CandidatePair, ConnectionState, FailureReason, GatheringState, IceCandidate,
+    PeerConnectionHandle, PeerConnectionHandler, RtcPeerConnection, SessionDescription,
+    SignalingState,
+};
+use crate::quota::QuotaScope;
+
+/// The invocations recorded by a mock handler, shared by its clones.
+pub struct Recorder<T> {
+    shared: Arc<(Mutex<Vec<T>>, Condvar)>,
+}
+
+impl<T> Clone for Recorder<T> {
+    fn clone(&self) -> Self {
+        Recorder {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T> Default for Recorder<T> {
+    fn default() -> Self {
+        Recorder {
+            shared: Arc::new((Mutex::new(Vec::new()), Condvar::new())),
+        }
+    }
+}
+
+impl<T> Recorder<T>
+where
+    T: Clone,
+{
+    /// Returns the invocations recorded so far, in order.
+    pub fn calls(&self) -> Vec<T> {
+        self.shared.0.lock().clone()
+    }
+
+    /// Returns the invocations recorded so far and forgets them.
+    pub fn take(&self) -> Vec<T> {
+        std::mem::take(&mut *self.shared.0.lock())
+    }
+
+    /// Waits until `pred` holds for the recorded invocations, returning `false` if it
+    /// still doesn't after `timeout`.
+    pub fn wait_for<F>(&self, timeout: Duration, pred: F) -> bool
+    where
+        F: Fn(&[T]) -> bool,
+    {
+        let deadline = Instant::now() + timeout;
+        let mut calls = self.shared.0.lock();
+        while !pred(&calls) {
+            if self.shared.1.wait_until(&mut calls, deadline).timed_out() {
+                return pred(&calls);
+            }
+        }
+        true
+    }
+
+    fn record(&self, call: T) {
+        self.shared.0.lock().push(call);
+        self.shared.1.notify_all();
+    }
+}
+
+/// An invocation of a [`MockDataChannel`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum DataChannelCall {
+    Created,
+    Open,
+    Closed,
+    Error(String),
+    Text(String),
+    Binary(Vec<u8>),
+    BufferedAmountLow,
+    Available,
+    QuotaExceeded(QuotaScope),
+}
+
+type Reply = Arc<dyn Fn(&[u8]) -> Option<Vec<u8>> + Send + Sync>;
+
+/// A [`DataChannelHandler`] recording its invocations, which can be scripted to send
+/// messages when opened or in reply to the ones it receives.
+///
+/// Clones share their recorder and script, so a clone can be kept to inspect a handler
+/// moved into a data channel.
+#[derive(Clone)]
+pub struct MockDataChannel {
+    calls: Recorder<DataChannelCall>,
+    handle: Option<DataChannelHandle>,
+    send_on_open: Arc<Vec<Vec<u8>>>,
+    reply: Option<Reply>,
+    subscriptions: Subscriptions,
+}
+
+impl Default for MockDataChannel {
+    fn default() -> Self {
+        MockDataChannel {
+            calls: Recorder::default(),
+            handle: None,
+            send_on_open: Arc::new(Vec::new()),
+            reply: None,
+            subscriptions: Subscriptions::all(),
+        }
+    }
+}
+
+impl MockDataChannel {
+    /// Sends `msg` once the data channel is open, after the previously scripted ones.
+    pub fn send_on_open(mut self, msg: &[u8]) -> Self {
+        Arc::make_mut(&mut self.send_on_open).push(msg.to_vec());
+        self
+    }
+
+    /// Answers received messages with the result of `f`, if any.
+    pub fn reply_with<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&[u8]) -> Option<Vec<u8>> + Send + Sync + 'static,
+    {
+        self.reply = Some(Arc::new(f));
+        self
+    }
+
+    pub fn subscriptions(mut self, subscriptions: Subscriptions) -> Self {
+        self.subscriptions = subscriptions;
+        self
+    }
+
+    pub fn calls(&self) -> Recorder<DataChannelCall> {
+        self.calls.clone()
+    }
+
+    fn send(&self, msg: &[u8]) {
+        if let Some(handle) = &self.handle {
+            if let Err(err) = handle.send(msg) {
+                log::warn!("MockDataChannel couldn't send message: {}", err);
+            }
+        }
+    }
+}
+
+impl DataChannelHandler for MockDataChannel {
+    fn on_created(&mut self, handle: DataChannelHandle) {
+        self.handle = Some(handle);
+        self.calls.record(DataChannelCall::Created);
+    }
+
+    fn on_open(&mut self) {
+        self.calls.record(DataChannelCall::Open);
+        for msg in self.send_on_open.iter() {
+            self.send(msg);
+        }
+    }
+
+    fn on_closed(&mut self) {
+        self.calls.record(DataChannelCall::Closed);
+    }
+
+    fn on_error(&mut self, err: &str) {
+        self.calls.record(DataChannelCall::Error(err.to_string()));
+    }
+
+    fn on_typed_message(&mut self, msg: Message) {
+        self.calls.record(match msg {
+            Message::Text(text) => DataChannelCall::Text(text.to_string()),
+            Message::Binary(data) => DataChannelCall::Binary(data.to_vec()),
+        });
+        if let Some(reply) = self.reply.as_ref().and_then(|f| f(msg.as_bytes())) {
+            self.send(&reply);
+        }
+    }
+
+    fn on_buffered_amount_low(&mut self) {
+        self.calls.record(DataChannelCall::BufferedAmountLow);
+    }
+
+    fn on_available(&mut self) {
+        self.calls.record(DataChannelCall::Available);
+    }
+
+    fn on_quota_exceeded(&mut self, scope: QuotaScope) {
+        self.calls.record(DataChannelCall::QuotaExceeded(scope));
+    }
+
+    fn subscriptions(&self) -> Subscriptions {
+        self.subscriptions
+    }
+}
+
+/// An invocation of a [`MockPeerConnection`].
+#[derive(Debug, Clone)]
+pub enum PeerConnectionCall {
+    Description(SessionDescription),
+    DescriptionDiff(SdpDiff),
+    Candidate(IceCandidate),
+    ConnectionStateChange(ConnectionState),
+    GatheringStateChange(GatheringState),
+    SignalingStateChange(SignalingState),
+    CandidatePairChange(Option<CandidatePair>, CandidatePair),
+    Failure(FailureReason),
+    /// A data channel opened by the remote peer, with its label.
+    DataChannel(String),
+}
+
+type MockChannel = Box<RtcDataChannel<MockDataChannel>>;
+type MockConnection = Box<RtcPeerConnection<MockPeerConnection>>;
+
+enum Signal {
+    Description(SessionDescription),
+    Candidate(IceCandidate),
+}
+
+/// A [`PeerConnectionHandler`] recording its invocations, which can be scripted to signal
+/// a remote connection and keeps the data channels it receives.
+///
+/// Clones share their state, so a clone can be kept to inspect a handler moved into a
+/// connection.
+#[derive(Clone, Default)]
+pub struct MockPeerConnection {
+    calls: Recorder<PeerConnectionCall>,
+    dc_handler: MockDataChannel,
+    data_channels: Arc<Mutex<Vec<MockChannel>>>,
+    signaling: Arc<Mutex<Option<Sender<Signal>>>>,
+}
+
+impl MockPeerConnection {
+    /// The handler of the incoming data channels, which share its recorder.
+    pub fn data_channel_handler(mut self, dc_handler: MockDataChannel) -> Self {
+        self.dc_handler = dc_handler;
+        self
+    }
+
+    pub fn calls(&self) -> Recorder<PeerConnectionCall> {
+        self.calls.clone()
+    }
+
+    /// Returns the incoming data channels, which are otherwise kept open.
+    pub fn take_data_channels(&self) -> Vec<MockChannel> {
+        std::mem::take(&mut *self.data_channels.lock())
+    }
+
+    /// Forwards the local descriptions and candidates to `remote`, from a dedicated
+    /// thread so that they are applied in order and outside of libdatachannel callbacks.
+    pub fn signal_to(&self, remote: PeerConnectionHandle) {
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            for signal in rx {
+                let res = match signal {
+                    Signal::Description(sess_desc) => remote.set_remote_description(&sess_desc),
+                    Signal::Candidate(cand) => remote.add_remote_candidate(&cand),
+                };
+                if let Err(err) = res {
+                    log::warn!("MockPeerConnection couldn't signal remote peer: {}", err);
+                }
+            }
+        });
+        *self.signaling.lock() = Some(tx);
+    }
+
+    fn signal(&self, signal: Signal) {
+        if let Some(tx) = &*self.signaling.lock() {
+            tx.send(signal).ok();
+        }
+    }
+}
+
+impl PeerConnectionHandler for MockPeerConnection {
+    type DCH = MockDataChannel;
+
+    fn data_channel_handler(&mut self) -> Self::DCH {
+        self.dc_handler.clone()
+    }
+
+    fn on_description(&mut self, sess_desc: SessionDescription) {
+        self.calls
+            .record(PeerConnectionCall::Description(sess_desc.clone()));
+        self.signal(Signal::Description(sess_desc));
+    }
+
+    fn on_description_diff(&mut self, diff: SdpDiff) {
+        self.calls.record(PeerConnectionCall::DescriptionDiff(diff));
+    }
+
+    fn on_candidate(&mut self, cand: IceCandidate) {
+        self.calls
+            .record(PeerConnectionCall::Candidate(cand.clone()));
+        self.signal(Signal::Candidate(cand));
+    }
+
+    fn on_connection_state_change(&mut self, state: ConnectionState) {
+        self.calls
+            .record(PeerConnectionCall::ConnectionStateChange(state));
+    }
+
+    fn on_gathering_state_change(&mut self, state: GatheringState) {
+        self.calls
+            .record(PeerConnectionCall::GatheringStateChange(state));
+    }
+
+    fn on_signaling_state_change(&mut self, state: SignalingState) {
+        self.calls
+            .record(PeerConnectionCall::SignalingStateChange(state));
+    }
+
+    fn on_candidate_pair_change(&mut self, old: Option<CandidatePair>, new: CandidatePair) {
+        self.calls
+            .record(PeerConnectionCall::CandidatePairChange(old, new));
+    }
+
+    fn on_failure(&mut self, reason: FailureReason) {
+        self.calls.record(PeerConnectionCall::Failure(reason));
+    }
+
+    fn on_data_channel(&mut self, data_channel: Box<RtcDataChannel<Self::DCH>>) {
+        self.calls
+            .record(PeerConnectionCall::DataChannel(data_channel.label()));
+        self.data_channels.lock().push(data_channel);
+    }
+}
+
+/// Creates two connections signaling each other, for tests that only need a working
+/// connection. Creating a data channel on the first one starts the negotiation.
+pub fn connected_pair(
+    config: &RtcConfig,
+    offerer: MockPeerConnection,
+    answerer: MockPeerConnection,
+) -> Result<(MockConnection, MockConnection)> {
+    let offerer_signaling = offerer.clone();
+    let answerer_signaling = answerer.clone();
+    let pc1 = RtcPeerConnection::new(config, offerer)?;
+    let pc2 = RtcPeerConnection::new(config, answerer)?;
+    offerer_signaling.signal_to(pc2.handle());
+    answerer_signaling.signal_to(pc1.handle());
+    Ok((pc1, pc2))
+}
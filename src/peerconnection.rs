@@ -1,19 +1,33 @@
-use std::ffi::{c_void, CStr, CString};
+use std::cell::Cell;
+use std::ffi::{c_void, CString};
 use std::fmt;
+#[cfg(feature = "async")]
+use std::future::Future;
 use std::os::raw::c_char;
 use std::ptr;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use datachannel_sys as sys;
 use derivative::Derivative;
-use parking_lot::ReentrantMutex;
+use parking_lot::{Condvar, Mutex, ReentrantMutex, RwLock};
 use serde::{Deserialize, Serialize};
+use webrtc_sdp::attribute_type::SdpAttribute;
 use webrtc_sdp::{parse_sdp, SdpSession};
 
-use crate::config::RtcConfig;
+use crate::config::{AddressFamily, CandidateFilter, RtcConfig};
 use crate::datachannel::{DataChannelHandler, DataChannelInit, RtcDataChannel};
+use crate::diff::{MediaSection, SdpDiff};
 use crate::error::{check, Error, Result};
-
-#[derive(Debug, PartialEq)]
+use crate::instrument::{event, Span};
+use crate::policy::FingerprintPolicy;
+use crate::quota::{Quota, QuotaTracker};
+use crate::registry;
+use crate::timer;
+use crate::track::{RtcTrack, TrackHandler};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
 pub enum ConnectionState {
     New,
     Connecting,
@@ -24,20 +38,24 @@ pub enum ConnectionState {
 }
 
 impl ConnectionState {
-    fn from_raw(state: sys::rtcState) -> Self {
-        match state {
+    fn from_raw(state: sys::rtcState) -> Option<Self> {
+        let state = match state {
             sys::rtcState_RTC_NEW => Self::New,
             sys::rtcState_RTC_CONNECTING => Self::Connecting,
             sys::rtcState_RTC_CONNECTED => Self::Connected,
             sys::rtcState_RTC_DISCONNECTED => Self::Disconnected,
             sys::rtcState_RTC_FAILED => Self::Failed,
             sys::rtcState_RTC_CLOSED => Self::Closed,
-            _ => panic!("Unknown rtcState: {}", state),
-        }
+            _ => {
+                log::warn!("Ignoring unknown rtcState: {}", state);
+                return None;
+            }
+        };
+        Some(state)
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
 pub enum GatheringState {
     New,
     InProgress,
@@ -45,17 +63,21 @@ pub enum GatheringState {
 }
 
 impl GatheringState {
-    fn from_raw(state: sys::rtcGatheringState) -> Self {
-        match state {
+    fn from_raw(state: sys::rtcGatheringState) -> Option<Self> {
+        let state = match state {
             sys::rtcGatheringState_RTC_GATHERING_NEW => Self::New,
             sys::rtcGatheringState_RTC_GATHERING_INPROGRESS => Self::InProgress,
             sys::rtcGatheringState_RTC_GATHERING_COMPLETE => Self::Complete,
-            _ => panic!("Unknown rtcGatheringState: {}", state),
-        }
+            _ => {
+                log::warn!("Ignoring unknown rtcGatheringState: {}", state);
+                return None;
+            }
+        };
+        Some(state)
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
 pub enum SignalingState {
     Stable,
     HaveLocalOffer,
@@ -65,25 +87,60 @@ pub enum SignalingState {
 }
 
 impl SignalingState {
-    fn from_raw(state: sys::rtcSignalingState) -> Self {
-        match state {
+    fn from_raw(state: sys::rtcSignalingState) -> Option<Self> {
+        let state = match state {
             sys::rtcSignalingState_RTC_SIGNALING_STABLE => Self::Stable,
             sys::rtcSignalingState_RTC_SIGNALING_HAVE_LOCAL_OFFER => Self::HaveLocalOffer,
             sys::rtcSignalingState_RTC_SIGNALING_HAVE_REMOTE_OFFER => Self::HaveRemoteOffer,
             sys::rtcSignalingState_RTC_SIGNALING_HAVE_LOCAL_PRANSWER => Self::HaveLocalPranswer,
             sys::rtcSignalingState_RTC_SIGNALING_HAVE_REMOTE_PRANSWER => Self::HaveRemotePranswer,
-            _ => panic!("Unknown rtcSignalingState: {}", state),
-        }
+            _ => {
+                log::warn!("Ignoring unknown rtcSignalingState: {}", state);
+                return None;
+            }
+        };
+        Some(state)
     }
 }
 
-#[derive(Debug, PartialEq, Hash)]
+/// Why a connection went to [`ConnectionState::Failed`].
+///
+/// [`ConnectTimeout`] and [`PeerUnresponsive`] are detected by this crate. libdatachannel
+/// doesn't report why a connection failed though, so the other reasons are a best guess
+/// from how far the connection got.
+///
+/// [`ConnectTimeout`]: FailureReason::ConnectTimeout
+/// [`PeerUnresponsive`]: FailureReason::PeerUnresponsive
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[non_exhaustive]
+pub enum FailureReason {
+    /// The connection didn't get connected within [`RtcConfig::connect_timeout`].
+    ///
+    /// [`RtcConfig::connect_timeout`]: crate::RtcConfig::connect_timeout
+    ConnectTimeout,
+    /// No data channel message was received from the peer within
+    /// [`RtcConfig::disconnect_timeout`], which is also the case of idle peers.
+    ///
+    /// [`RtcConfig::disconnect_timeout`]: crate::RtcConfig::disconnect_timeout
+    PeerUnresponsive,
+    /// No candidate pair was selected before failing, most likely because ICE checks
+    /// were exhausted, e.g. because of a firewall or a missing TURN server.
+    IceFailed,
+    /// A candidate pair was selected but the connection never got connected, most likely
+    /// because the DTLS handshake failed (e.g. the certificate of the peer doesn't match
+    /// the fingerprint of its description).
+    HandshakeFailed,
+    /// The connection failed after getting connected, whatever the cause.
+    ConnectionLost,
+}
+
+#[derive(Debug, Clone, PartialEq, Hash)]
 pub struct CandidatePair {
     pub local: String,
     pub remote: String,
 }
 
-#[derive(Derivative, Serialize, Deserialize)]
+#[derive(Derivative, Clone, Serialize, Deserialize)]
 #[derivative(Debug)]
 pub struct SessionDescription {
     #[derivative(Debug(format_with = "fmt_sdp"))]
@@ -139,16 +196,16 @@ impl SdpType {
             "offer" => Ok(Self::Offer),
             "pranswer" => Ok(Self::Pranswer),
             "rollback" => Ok(Self::Rollback),
-            _ => Err(Error::InvalidArg),
+            _ => Err(Error::BadSdp(format!("Unknown SdpType: {}", val))),
         }
     }
 
     fn val(&self) -> &'static str {
         match self {
-            Self::Answer => "answer,",
-            Self::Offer => "offer,",
-            Self::Pranswer => "pranswer,",
-            Self::Rollback => "rollback,",
+            Self::Answer => "answer",
+            Self::Offer => "offer",
+            Self::Pranswer => "pranswer",
+            Self::Rollback => "rollback",
         }
     }
 }
@@ -160,6 +217,367 @@ pub struct IceCandidate {
     pub mid: String,
 }
 
+impl IceCandidate {
+    /// The candidate type: `host`, `srflx`, `prflx` or `relay`.
+    pub fn candidate_type(&self) -> Option<&str> {
+        let mut fields = self.candidate.split_whitespace();
+        fields.find(|field| *field == "typ")?;
+        fields.next()
+    }
+
+    /// The connection address, an IP address or an mDNS host name.
+    pub fn address(&self) -> Option<&str> {
+        self.candidate.split_whitespace().nth(4)
+    }
+
+    /// The address of the host candidate a server reflexive, peer reflexive or relay
+    /// candidate derives from (`raddr`), when advertised.
+    pub fn related_address(&self) -> Option<&str> {
+        let mut fields = self.candidate.split_whitespace();
+        fields.find(|field| *field == "raddr")?;
+        fields.next()
+    }
+}
+
+/// Removes the `a=candidate` lines of `sdp` rejected by `filter`.
+fn filter_candidates(sdp: &str, filter: &dyn Fn(&IceCandidate) -> bool) -> String {
+    // The mid of each media section, which may come after its candidates
+    let mut mids = vec![String::new()];
+    for line in sdp.lines() {
+        if line.starts_with("m=") {
+            mids.push(String::new());
+        } else if let Some(mid) = line.trim_end().strip_prefix("a=mid:") {
+            *mids.last_mut().unwrap() = mid.to_string();
+        }
+    }
+
+    let mut section = 0;
+    let mut filtered = String::with_capacity(sdp.len());
+    for line in sdp.lines() {
+        if line.starts_with("m=") {
+            section += 1;
+        } else if let Some(candidate) = line.trim_end().strip_prefix("a=") {
+            if candidate.starts_with("candidate:") {
+                let cand = IceCandidate {
+                    candidate: candidate.to_string(),
+                    mid: mids[section].clone(),
+                };
+                if !filter(&cand) {
+                    continue;
+                }
+            }
+        }
+        filtered.push_str(line.trim_end());
+        filtered.push_str("\r\n");
+    }
+    filtered
+}
+
+/// A DTLS certificate fingerprint, as advertised with `a=fingerprint` in a description.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fingerprint {
+    /// The hash function, e.g. `sha-256`.
+    pub algorithm: String,
+    /// Colon separated uppercase hex bytes.
+    pub value: String,
+}
+
+impl Fingerprint {
+    /// Compares with an expected fingerprint value, ignoring case.
+    pub fn matches(&self, value: &str) -> bool {
+        self.value.eq_ignore_ascii_case(value.trim())
+    }
+}
+
+impl SessionDescription {
+    /// Returns the DTLS certificate fingerprint advertised in this description, at the
+    /// session level or else in the first media section carrying one.
+    ///
+    /// libdatachannel rejects the DTLS handshake when the remote certificate doesn't
+    /// match the fingerprint of the remote description, so checking the latter is
+    /// enough to authenticate the peer.
+    pub fn fingerprint(&self) -> Option<Fingerprint> {
+        let media = self
+            .sdp
+            .media
+            .iter()
+            .flat_map(|media| media.get_attributes());
+        self.sdp
+            .attribute
+            .iter()
+            .chain(media)
+            .find_map(|attribute| match attribute {
+                SdpAttribute::Fingerprint(fingerprint) => Some(Fingerprint {
+                    algorithm: fingerprint.hash_algorithm.to_string(),
+                    value: fingerprint
+                        .fingerprint
+                        .iter()
+                        .map(|byte| format!("{:02X}", byte))
+                        .collect::<Vec<_>>()
+                        .join(":"),
+                }),
+                _ => None,
+            })
+    }
+}
+
+type FingerprintVerifier = Box<dyn Fn(&Fingerprint) -> bool + Send + Sync>;
+
+/// The maximum message size of libdatachannel, when not configured.
+const DEFAULT_LOCAL_MAX_MESSAGE_SIZE: usize = 256 * 1024;
+/// The maximum message size assumed when the remote description doesn't advertise one.
+const DEFAULT_REMOTE_MAX_MESSAGE_SIZE: usize = 64 * 1024;
+
+/// State shared between a connection and its data channels.
+pub(crate) struct ConnectionContext {
+    pub(crate) id: i32,
+    local_max_message_size: usize,
+    pub(crate) quota: Mutex<Option<QuotaTracker>>,
+    pub(crate) span: Span,
+    /// Nanoseconds spent in callbacks, see `RtcPeerConnection::callback_time`.
+    callback_time: AtomicU64,
+    created: Instant,
+    /// Nanoseconds between `created` and the last received message.
+    last_received: AtomicU64,
+    gathering_state: Mutex<GatheringState>,
+    gathering_complete: Condvar,
+    /// Dropped once gathering completes, which wakes up their receivers.
+    #[cfg(feature = "async")]
+    gathering_waiters: Mutex<Vec<async_channel::Sender<()>>>,
+    /// Serializes the calls to the handler of the connection.
+    pub(crate) handler_lock: ReentrantMutex<()>,
+    /// Address of the handler of the `RtcPeerConnection`, zeroed when it is dropped.
+    /// Held while timer tasks use the handler, which delays its drop until they are done.
+    owner: ReentrantMutex<Cell<usize>>,
+    connected: AtomicBool,
+    deleted: AtomicBool,
+    closed_notified: AtomicBool,
+    failure_reason: Mutex<Option<FailureReason>>,
+    disconnect_timeout: Option<Duration>,
+    /// Whether the liveness check is scheduled.
+    watching: AtomicBool,
+    /// Whether the peer stopped responding, as notified by the liveness check.
+    unresponsive: AtomicBool,
+    /// The last selected candidate pair notified to the handler.
+    candidate_pair: Mutex<Option<CandidatePair>>,
+    candidate_pair_interval: Option<Duration>,
+    /// Whether the candidate pair check is scheduled.
+    checking_pair: AtomicBool,
+    signaling_state: Mutex<SignalingState>,
+    /// Renegotiation requested while an offer was pending.
+    negotiation_needed: AtomicBool,
+    /// Timer task closing the connection, see `close_deferred`.
+    close_task: unsafe fn(&Arc<ConnectionContext>, usize),
+}
+
+impl ConnectionContext {
+    fn new(
+        id: i32,
+        config: &RtcConfig,
+        close_task: unsafe fn(&Arc<ConnectionContext>, usize),
+    ) -> Self {
+        let max_message_size = config.max_message_size;
+        ConnectionContext {
+            id,
+            local_max_message_size: match max_message_size {
+                size if size > 0 => size as usize,
+                _ => DEFAULT_LOCAL_MAX_MESSAGE_SIZE,
+            },
+            quota: Mutex::new(None),
+            span: Span::connection(id),
+            callback_time: AtomicU64::new(0),
+            created: Instant::now(),
+            last_received: AtomicU64::new(0),
+            gathering_state: Mutex::new(GatheringState::New),
+            gathering_complete: Condvar::new(),
+            #[cfg(feature = "async")]
+            gathering_waiters: Mutex::new(Vec::new()),
+            handler_lock: ReentrantMutex::new(()),
+            owner: ReentrantMutex::new(Cell::new(0)),
+            connected: AtomicBool::new(false),
+            deleted: AtomicBool::new(false),
+            closed_notified: AtomicBool::new(false),
+            failure_reason: Mutex::new(None),
+            disconnect_timeout: config.disconnect_timeout,
+            watching: AtomicBool::new(false),
+            unresponsive: AtomicBool::new(false),
+            candidate_pair: Mutex::new(None),
+            candidate_pair_interval: config.candidate_pair_interval,
+            checking_pair: AtomicBool::new(false),
+            signaling_state: Mutex::new(SignalingState::Stable),
+            negotiation_needed: AtomicBool::new(false),
+            close_task,
+        }
+    }
+
+    /// Sends a new offer if a renegotiation is needed and no offer is pending.
+    fn negotiate_if_needed(&self) -> Result<()> {
+        if *self.signaling_state.lock() != SignalingState::Stable
+            || !self.negotiation_needed.swap(false, Ordering::SeqCst)
+        {
+            return Ok(());
+        }
+        let _span = self.span.entered();
+        event!(INFO, "Renegotiating");
+        let sdp_type = CString::new(SdpType::Offer.val())?;
+        check(unsafe { sys::rtcSetLocalDescription(self.id, sdp_type.as_ptr()) })?;
+        Ok(())
+    }
+
+    /// Deletes the connection in libdatachannel, which stops its callbacks. Only the
+    /// first call has an effect.
+    fn delete(&self) {
+        if self.deleted.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        if let Err(err) = check(unsafe { sys::rtcDeletePeerConnection(self.id) }) {
+            log::error!(
+                "Error while deleting RtcPeerConnection id={}: {}",
+                self.id,
+                err
+            );
+        }
+    }
+
+    fn is_deleted(&self) -> bool {
+        self.deleted.load(Ordering::SeqCst)
+    }
+
+    /// Returns whether the closed state change still has to be notified, and marks it as
+    /// notified.
+    fn notify_closed(&self) -> bool {
+        !self.closed_notified.swap(true, Ordering::SeqCst)
+    }
+
+    /// Whether received messages are tracked for [`RtcConfig::disconnect_timeout`].
+    pub(crate) fn watches_liveness(&self) -> bool {
+        self.disconnect_timeout.is_some()
+    }
+
+    pub(crate) fn record_received(&self) {
+        let elapsed = self.created.elapsed().as_nanos() as u64;
+        self.last_received.store(elapsed, Ordering::Relaxed);
+    }
+
+    /// Time elapsed since the last received message.
+    fn idle_time(&self) -> Duration {
+        let last_received = Duration::from_nanos(self.last_received.load(Ordering::Relaxed));
+        self.created.elapsed().saturating_sub(last_received)
+    }
+
+    /// The largest message that can be received.
+    pub(crate) fn local_max_message_size(&self) -> usize {
+        self.local_max_message_size
+    }
+
+    /// The largest message that can be sent, the smallest of the local maximum and of the
+    /// one advertised by the remote description (`a=max-message-size`).
+    pub(crate) fn max_message_size(&self) -> usize {
+        let remote = read_string_ffi(self.id, sys::rtcGetRemoteDescription, "remote_description")
+            .and_then(|sdp| {
+                sdp.lines()
+                    .find_map(|line| line.strip_prefix("a=max-message-size:"))
+                    .and_then(|size| size.trim().parse::<usize>().ok())
+            });
+        let remote = match remote {
+            // Zero means the remote peer doesn't have a limit
+            Some(0) => usize::MAX,
+            Some(size) => size,
+            None => DEFAULT_REMOTE_MAX_MESSAGE_SIZE,
+        };
+        remote.min(self.local_max_message_size)
+    }
+
+    /// Runs `task` at `at` with the context and the address of the handler of the
+    /// connection, unless it has been dropped.
+    ///
+    /// Tasks run on the timer thread, they must only use the handler while holding
+    /// `handler_lock`, and the connection itself through this context.
+    fn schedule(self: &Arc<Self>, at: Instant, task: unsafe fn(&Arc<Self>, usize)) {
+        let context = Arc::downgrade(self);
+        timer::schedule(at, move || {
+            if let Some(context) = context.upgrade() {
+                let owner = context.owner.lock();
+                if owner.get() != 0 {
+                    unsafe { task(&context, owner.get()) }
+                }
+            }
+        })
+    }
+
+    /// Closes the connection from the timer thread, for callbacks in which it can't be
+    /// deleted. The handler gets a [`ConnectionState::Closed`] state change unless it
+    /// already got one.
+    pub(crate) fn close_deferred(self: &Arc<Self>) {
+        self.schedule(Instant::now(), self.close_task)
+    }
+
+    fn gathering_state(&self) -> GatheringState {
+        *self.gathering_state.lock()
+    }
+
+    fn set_gathering_state(&self, state: GatheringState) {
+        *self.gathering_state.lock() = state;
+        if state == GatheringState::Complete {
+            self.gathering_complete.notify_all();
+            #[cfg(feature = "async")]
+            self.gathering_waiters.lock().clear();
+        }
+    }
+
+    /// Returns whether gathering completed within `timeout`.
+    fn wait_gathering_complete(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        let mut state = self.gathering_state.lock();
+        while *state != GatheringState::Complete {
+            if self
+                .gathering_complete
+                .wait_until(&mut state, deadline)
+                .timed_out()
+            {
+                return *state == GatheringState::Complete;
+            }
+        }
+        true
+    }
+
+    /// Returns a receiver closed once gathering completes, or `None` if it already has.
+    #[cfg(feature = "async")]
+    fn gathering_complete(&self) -> Option<async_channel::Receiver<()>> {
+        // Holding the state lock orders the registration with `set_gathering_state`
+        let state = self.gathering_state.lock();
+        if *state == GatheringState::Complete {
+            return None;
+        }
+        let (tx, rx) = async_channel::bounded(1);
+        self.gathering_waiters.lock().push(tx);
+        Some(rx)
+    }
+
+    /// Returns a guard accounting the time until it is dropped to the connection.
+    pub(crate) fn timer(self: &Arc<Self>) -> CallbackTimer {
+        CallbackTimer {
+            context: self.clone(),
+            start: Instant::now(),
+        }
+    }
+}
+
+pub(crate) struct CallbackTimer {
+    context: Arc<ConnectionContext>,
+    start: Instant,
+}
+
+impl Drop for CallbackTimer {
+    fn drop(&mut self) {
+        let elapsed = self.start.elapsed();
+        self.context
+            .callback_time
+            .fetch_add(elapsed.as_nanos() as u64, Ordering::Relaxed);
+        registry::add_callback_time(self.context.id, elapsed);
+    }
+}
+
 #[allow(unused_variables)]
 pub trait PeerConnectionHandler {
     type DCH;
@@ -167,17 +585,37 @@ pub trait PeerConnectionHandler {
     fn data_channel_handler(&mut self) -> Self::DCH;
 
     fn on_description(&mut self, sess_desc: SessionDescription) {}
+
+    /// Called right before [`on_description`] when renegotiating, with what changed from
+    /// the previous local description, see [`SessionDescription::diff`].
+    ///
+    /// [`on_description`]: PeerConnectionHandler::on_description
+    fn on_description_diff(&mut self, diff: SdpDiff) {}
+
     fn on_candidate(&mut self, cand: IceCandidate) {}
     fn on_connection_state_change(&mut self, state: ConnectionState) {}
     fn on_gathering_state_change(&mut self, state: GatheringState) {}
     fn on_signaling_state_change(&mut self, state: SignalingState) {}
+    fn on_candidate_pair_change(&mut self, old: Option<CandidatePair>, new: CandidatePair) {}
+    /// Called with why the connection failed, right before the change to
+    /// [`ConnectionState::Failed`].
+    fn on_failure(&mut self, reason: FailureReason) {}
     fn on_data_channel(&mut self, data_channel: Box<RtcDataChannel<Self::DCH>>) {}
 }
 
 pub struct RtcPeerConnection<P> {
-    lock: ReentrantMutex<()>,
     id: i32,
     pc_handler: P,
+    context: Arc<ConnectionContext>,
+    handle: PeerConnectionHandle,
+    disable_trickle: bool,
+    /// Local description held back until gathering completes, without trickle.
+    pending_description: bool,
+    /// The last local description passed to the handler, to diff the next one with.
+    last_description: Option<SessionDescription>,
+    /// Whether data channels and tracks added after connecting trigger a renegotiation
+    /// from this crate, in place of the automatic one of libdatachannel.
+    renegotiate_added: bool,
 }
 
 impl<P> RtcPeerConnection<P>
@@ -186,18 +624,40 @@ where
     P::DCH: DataChannelHandler + Send,
 {
     pub fn new(config: &RtcConfig, pc_handler: P) -> Result<Box<Self>> {
-        crate::ensure_logging();
+        crate::logging::ensure_logging();
 
         unsafe {
             let id = check(sys::rtcCreatePeerConnection(&config.as_raw()))?;
             let mut rtc_pc = Box::new(RtcPeerConnection {
-                lock: ReentrantMutex::new(()),
                 id,
                 pc_handler,
+                context: Arc::new(ConnectionContext::new(
+                    id,
+                    config,
+                    RtcPeerConnection::<P>::close_task,
+                )),
+                handle: PeerConnectionHandle {
+                    inner: Arc::new(HandleInner {
+                        id,
+                        verifier: RwLock::new(None),
+                        policy: RwLock::new(None),
+                        candidate_filter: config.candidate_filter.clone(),
+                        address_family: config.address_family,
+                    }),
+                },
+                disable_trickle: config.disable_trickle,
+                pending_description: false,
+                last_description: None,
+                renegotiate_added: config.disable_auto_negotiation,
             });
+            crate::object_created();
             let ptr = &mut *rtc_pc;
 
             sys::rtcSetUserPointer(id, ptr as *mut _ as *mut c_void);
+            registry::add_connection(id);
+
+            let _span = rtc_pc.context.span.entered();
+            event!(INFO, "Peer connection created");
 
             check(sys::rtcSetLocalDescriptionCallback(
                 id,
@@ -229,6 +689,15 @@ where
                 Some(RtcPeerConnection::<P>::data_channel_cb),
             ))?;
 
+            let ptr = &mut rtc_pc.pc_handler as *mut P as usize;
+            rtc_pc.context.owner.lock().set(ptr);
+            if let Some(timeout) = config.connect_timeout {
+                rtc_pc.context.schedule(
+                    Instant::now() + timeout,
+                    RtcPeerConnection::<P>::connect_timeout,
+                );
+            }
+
             Ok(rtc_pc)
         }
     }
@@ -240,8 +709,9 @@ where
         ptr: *mut c_void,
     ) {
         let rtc_pc = &mut *(ptr as *mut RtcPeerConnection<P>);
+        let _timer = rtc_pc.context.timer();
 
-        let sdp = CStr::from_ptr(sdp).to_string_lossy();
+        let sdp = rtc_pc.handle.inner.filter_sdp(crate::ffi_str(sdp));
         let sdp = match parse_sdp(&sdp, false) {
             Ok(sdp) => sdp,
             Err(err) => {
@@ -251,7 +721,7 @@ where
             }
         };
 
-        let sdp_type = CStr::from_ptr(sdp_type).to_string_lossy();
+        let sdp_type = crate::ffi_str(sdp_type);
         let sdp_type = match SdpType::from(&sdp_type) {
             Ok(sdp_type) => sdp_type,
             Err(_) => {
@@ -261,10 +731,33 @@ where
             }
         };
 
+        let _span = rtc_pc.context.span.entered();
+        event!(DEBUG, ?sdp_type, "Local description");
+        if rtc_pc.disable_trickle && rtc_pc.context.gathering_state() != GatheringState::Complete {
+            rtc_pc.pending_description = true;
+            return;
+        }
         let sess_desc = SessionDescription { sdp, sdp_type };
 
-        let _guard = rtc_pc.lock.lock();
-        rtc_pc.pc_handler.on_description(sess_desc);
+        let _guard = rtc_pc.context.handler_lock.lock();
+        Self::deliver_description(
+            &mut rtc_pc.last_description,
+            &mut rtc_pc.pc_handler,
+            sess_desc,
+        );
+    }
+
+    /// Passes a local description to the handler, preceded by its changes from the
+    /// previous one when renegotiating. Must be called while holding `handler_lock`.
+    fn deliver_description(
+        last: &mut Option<SessionDescription>,
+        pc_handler: &mut P,
+        sess_desc: SessionDescription,
+    ) {
+        if let Some(previous) = last.replace(sess_desc.clone()) {
+            pc_handler.on_description_diff(sess_desc.diff(&previous));
+        }
+        pc_handler.on_description(sess_desc);
     }
 
     unsafe extern "C" fn local_candidate_cb(
@@ -274,52 +767,267 @@ where
         ptr: *mut c_void,
     ) {
         let rtc_pc = &mut *(ptr as *mut RtcPeerConnection<P>);
+        let _timer = rtc_pc.context.timer();
+        if rtc_pc.disable_trickle {
+            return;
+        }
 
-        let candidate = CStr::from_ptr(cand).to_string_lossy().to_string();
-        let mid = CStr::from_ptr(mid).to_string_lossy().to_string();
+        let candidate = crate::ffi_str(cand);
+        let mid = crate::ffi_str(mid);
         let cand = IceCandidate { candidate, mid };
+        if !rtc_pc.handle.inner.keep_candidate(&cand) {
+            return;
+        }
+
+        let _span = rtc_pc.context.span.entered();
+        event!(DEBUG, candidate = %cand.candidate, "Local candidate");
 
-        let _guard = rtc_pc.lock.lock();
+        let _guard = rtc_pc.context.handler_lock.lock();
         rtc_pc.pc_handler.on_candidate(cand);
     }
 
-    unsafe extern "C" fn state_change_cb(_: i32, state: sys::rtcState, ptr: *mut c_void) {
+    unsafe extern "C" fn state_change_cb(id: i32, state: sys::rtcState, ptr: *mut c_void) {
         let rtc_pc = &mut *(ptr as *mut RtcPeerConnection<P>);
+        let _timer = rtc_pc.context.timer();
 
-        let state = ConnectionState::from_raw(state);
+        let state = match ConnectionState::from_raw(state) {
+            Some(state) => state,
+            None => return,
+        };
+        registry::update_connection(id, |info| info.state = state);
+
+        // Connected means that both the ICE and DTLS handshakes succeeded
+        let _span = rtc_pc.context.span.entered();
+        event!(INFO, ?state, "Connection state changed");
 
-        let _guard = rtc_pc.lock.lock();
+        let _guard = rtc_pc.context.handler_lock.lock();
+        let connected = state == ConnectionState::Connected;
+        if connected {
+            rtc_pc.context.connected.store(true, Ordering::SeqCst);
+        }
+        if state == ConnectionState::Closed {
+            rtc_pc.context.closed_notified.store(true, Ordering::SeqCst);
+        }
+        if state == ConnectionState::Failed {
+            let reason = rtc_pc.infer_failure_reason();
+            event!(WARN, ?reason, "Connection failed");
+            *rtc_pc.context.failure_reason.lock() = Some(reason);
+            rtc_pc.pc_handler.on_failure(reason);
+        }
         rtc_pc.pc_handler.on_connection_state_change(state);
+        if connected {
+            Self::update_candidate_pair(&rtc_pc.context, &mut rtc_pc.pc_handler);
+            if let Some(interval) = rtc_pc.context.candidate_pair_interval {
+                if !rtc_pc.context.checking_pair.swap(true, Ordering::SeqCst) {
+                    rtc_pc.context.schedule(
+                        Instant::now() + interval,
+                        RtcPeerConnection::<P>::poll_candidate_pair,
+                    );
+                }
+            }
+
+            if let Some(timeout) = rtc_pc.context.disconnect_timeout {
+                if !rtc_pc.context.watching.swap(true, Ordering::SeqCst) {
+                    rtc_pc.context.record_received();
+                    rtc_pc.context.schedule(
+                        Instant::now() + timeout / 10,
+                        RtcPeerConnection::<P>::check_liveness,
+                    );
+                }
+            }
+        }
     }
 
-    unsafe extern "C" fn gathering_state_cb(_: i32, state: sys::rtcState, ptr: *mut c_void) {
-        let rtc_pc = &mut *(ptr as *mut RtcPeerConnection<P>);
+    /// libdatachannel doesn't say why a connection failed, it is guessed from how far it
+    /// got, see [`FailureReason`].
+    fn infer_failure_reason(&self) -> FailureReason {
+        if self.context.connected.load(Ordering::SeqCst) {
+            FailureReason::ConnectionLost
+        } else if self.selected_candidate_pair().is_some() {
+            FailureReason::HandshakeFailed
+        } else {
+            FailureReason::IceFailed
+        }
+    }
+
+    unsafe fn check_liveness(context: &Arc<ConnectionContext>, handler: usize) {
+        let timeout = match context.disconnect_timeout {
+            Some(timeout)
+                if !context.is_deleted() && !context.closed_notified.load(Ordering::SeqCst) =>
+            {
+                timeout
+            }
+            _ => return,
+        };
+        let _timer = context.timer();
+        let _span = context.span.entered();
+
+        let idle_time = context.idle_time();
+        if idle_time >= timeout {
+            event!(WARN, "Peer unresponsive");
+            log::warn!(
+                "RtcPeerConnection id={} got nothing from its peer for {:?}",
+                context.id,
+                idle_time
+            );
+            return Self::fail(context, handler, FailureReason::PeerUnresponsive);
+        }
 
-        let state = GatheringState::from_raw(state);
+        let unresponsive = idle_time >= timeout / 2;
+        if unresponsive != context.unresponsive.swap(unresponsive, Ordering::SeqCst) {
+            let state = if unresponsive {
+                ConnectionState::Disconnected
+            } else {
+                ConnectionState::Connected
+            };
+            event!(INFO, ?state, "Connection liveness changed");
+            registry::update_connection(context.id, |info| info.state = state);
+
+            let _guard = context.handler_lock.lock();
+            let handler = &mut *(handler as *mut P);
+            handler.on_connection_state_change(state);
+        }
 
-        let _guard = rtc_pc.lock.lock();
+        context.schedule(
+            Instant::now() + timeout / 10,
+            RtcPeerConnection::<P>::check_liveness,
+        );
+    }
+
+    unsafe fn poll_candidate_pair(context: &Arc<ConnectionContext>, handler: usize) {
+        let interval = match context.candidate_pair_interval {
+            Some(interval)
+                if !context.is_deleted() && !context.closed_notified.load(Ordering::SeqCst) =>
+            {
+                interval
+            }
+            _ => return,
+        };
+        let _timer = context.timer();
+        let _span = context.span.entered();
+
+        let _guard = context.handler_lock.lock();
+        Self::update_candidate_pair(context, &mut *(handler as *mut P));
+        context.schedule(
+            Instant::now() + interval,
+            RtcPeerConnection::<P>::poll_candidate_pair,
+        );
+    }
+
+    unsafe fn connect_timeout(context: &Arc<ConnectionContext>, handler: usize) {
+        if context.connected.load(Ordering::SeqCst) || context.is_deleted() {
+            return;
+        }
+        let _timer = context.timer();
+
+        let _span = context.span.entered();
+        event!(WARN, "Connection timed out");
+        log::warn!(
+            "RtcPeerConnection id={} timed out while connecting",
+            context.id
+        );
+        Self::fail(context, handler, FailureReason::ConnectTimeout);
+    }
+
+    /// Fails the connection from a timer task, libdatachannel doesn't report anything
+    /// once it is deleted.
+    unsafe fn fail(context: &ConnectionContext, handler: usize, reason: FailureReason) {
+        // Stops libdatachannel from connecting afterwards
+        context.delete();
+        *context.failure_reason.lock() = Some(reason);
+        let state = ConnectionState::Failed;
+        registry::update_connection(context.id, |info| info.state = state);
+
+        let _guard = context.handler_lock.lock();
+        let handler = &mut *(handler as *mut P);
+        handler.on_failure(reason);
+        handler.on_connection_state_change(state);
+    }
+
+    unsafe extern "C" fn gathering_state_cb(id: i32, state: sys::rtcState, ptr: *mut c_void) {
+        let rtc_pc = &mut *(ptr as *mut RtcPeerConnection<P>);
+        let _timer = rtc_pc.context.timer();
+
+        let state = match GatheringState::from_raw(state) {
+            Some(state) => state,
+            None => return,
+        };
+        registry::update_connection(id, |info| info.gathering_state = state);
+        rtc_pc.context.set_gathering_state(state);
+
+        let _span = rtc_pc.context.span.entered();
+        event!(INFO, ?state, "Gathering state changed");
+
+        let _guard = rtc_pc.context.handler_lock.lock();
+        if state == GatheringState::Complete && rtc_pc.pending_description {
+            rtc_pc.pending_description = false;
+            // The current local description embeds all the gathered candidates
+            match rtc_pc.handle.local_description() {
+                Some(sess_desc) => Self::deliver_description(
+                    &mut rtc_pc.last_description,
+                    &mut rtc_pc.pc_handler,
+                    sess_desc,
+                ),
+                None => log::warn!("No local description to send once gathering completed"),
+            }
+        }
         rtc_pc.pc_handler.on_gathering_state_change(state);
     }
 
-    unsafe extern "C" fn signaling_state_cb(_: i32, state: sys::rtcState, ptr: *mut c_void) {
+    unsafe extern "C" fn signaling_state_cb(id: i32, state: sys::rtcState, ptr: *mut c_void) {
         let rtc_pc = &mut *(ptr as *mut RtcPeerConnection<P>);
+        let _timer = rtc_pc.context.timer();
+
+        let state = match SignalingState::from_raw(state) {
+            Some(state) => state,
+            None => return,
+        };
+        registry::update_connection(id, |info| info.signaling_state = state);
 
-        let state = SignalingState::from_raw(state);
+        let _span = rtc_pc.context.span.entered();
+        event!(DEBUG, ?state, "Signaling state changed");
 
-        let _guard = rtc_pc.lock.lock();
+        *rtc_pc.context.signaling_state.lock() = state;
+        let _guard = rtc_pc.context.handler_lock.lock();
         rtc_pc.pc_handler.on_signaling_state_change(state);
+
+        // The offer is created from the timer thread rather than within this callback
+        if state == SignalingState::Stable
+            && rtc_pc.context.negotiation_needed.load(Ordering::SeqCst)
+        {
+            rtc_pc
+                .context
+                .schedule(Instant::now(), RtcPeerConnection::<P>::pending_negotiation);
+        }
+    }
+
+    unsafe fn pending_negotiation(context: &Arc<ConnectionContext>, _: usize) {
+        if context.is_deleted() {
+            return;
+        }
+        if let Err(err) = context.negotiate_if_needed() {
+            log::error!(
+                "RtcPeerConnection id={} couldn't renegotiate: {}",
+                context.id,
+                err
+            );
+        }
     }
 
     unsafe extern "C" fn data_channel_cb(_: i32, id: i32, ptr: *mut c_void) {
         let rtc_pc = &mut *(ptr as *mut RtcPeerConnection<P>);
+        let _timer = rtc_pc.context.timer();
 
-        let guard = rtc_pc.lock.lock();
+        let _span = rtc_pc.context.span.entered();
+        event!(DEBUG, dc_id = id, "Incoming data channel");
+
+        let guard = rtc_pc.context.handler_lock.lock();
         let dc = rtc_pc.pc_handler.data_channel_handler();
         drop(guard);
 
-        match RtcDataChannel::new(id, dc) {
+        match RtcDataChannel::new(id, dc, rtc_pc.context.clone(), None) {
             Ok(dc) => {
-                let _guard = rtc_pc.lock.lock();
+                let _guard = rtc_pc.context.handler_lock.lock();
                 rtc_pc.pc_handler.on_data_channel(dc);
             }
             Err(err) => log::error!(
@@ -342,7 +1050,9 @@ where
     {
         let label = CString::new(label)?;
         let id = check(unsafe { sys::rtcCreateDataChannel(self.id, label.as_ptr()) })?;
-        RtcDataChannel::new(id, dc_handler)
+        let dc = RtcDataChannel::new(id, dc_handler, self.context.clone(), None)?;
+        self.renegotiate_if_missing(|section| section.kind == "application")?;
+        Ok(dc)
     }
 
     pub fn create_data_channel_ex<C>(
@@ -358,32 +1068,364 @@ where
         let id = check(unsafe {
             sys::rtcCreateDataChannelEx(self.id, label.as_ptr(), &dc_init.as_raw()?)
         })?;
-        RtcDataChannel::new(id, dc_handler)
+        let initial_message = dc_init.initial_message.clone();
+        let dc = RtcDataChannel::new(id, dc_handler, self.context.clone(), initial_message)?;
+        self.renegotiate_if_missing(|section| section.kind == "application")?;
+        Ok(dc)
+    }
+
+    /// Adds a media track described by an SDP media section (`m=` line and attributes),
+    /// which can be built with [`MediaDescription`].
+    ///
+    /// [`MediaDescription`]: crate::MediaDescription
+    ///
+    /// Returns [`Error::FeatureDisabled`] when built without the `media` feature.
+    pub fn add_track<T>(&mut self, description: &str, t_handler: T) -> Result<Box<RtcTrack<T>>>
+    where
+        T: TrackHandler + Send,
+    {
+        let track = RtcTrack::new(self.id, description, t_handler)?;
+        let mid = description
+            .lines()
+            .find_map(|line| line.trim_end().strip_prefix("a=mid:"))
+            .map(str::to_string);
+        if let Some(mid) = mid {
+            self.renegotiate_if_missing(|section| section.mid == mid)?;
+        }
+        Ok(track)
+    }
+
+    /// Sends a new offer through [`PeerConnectionHandler::on_description`], so that the
+    /// peer learns about data channels and tracks added since the connection was
+    /// negotiated, while the existing ones are kept.
+    ///
+    /// If an offer is pending, the renegotiation happens once the signaling state is back
+    /// to stable.
+    ///
+    /// libdatachannel renegotiates by itself when needed, unless
+    /// [`RtcConfig::disable_auto_negotiation`] is set. In that case [`create_data_channel`]
+    /// and [`add_track`] call this when the new channel or track isn't negotiated yet,
+    /// once the application has negotiated the connection.
+    ///
+    /// [`create_data_channel`]: RtcPeerConnection::create_data_channel
+    /// [`add_track`]: RtcPeerConnection::add_track
+    /// [`RtcConfig::disable_auto_negotiation`]: crate::RtcConfig::disable_auto_negotiation
+    pub fn renegotiate(&mut self) -> Result<()> {
+        self.context
+            .negotiation_needed
+            .store(true, Ordering::SeqCst);
+        self.context.negotiate_if_needed()
+    }
+
+    /// Renegotiates if the connection was negotiated without a media section matching
+    /// `negotiated`, only with auto-negotiation disabled so that libdatachannel doesn't
+    /// send a second offer. Before the first negotiation there is nothing to do, the
+    /// initial offer includes everything.
+    fn renegotiate_if_missing<F>(&mut self, negotiated: F) -> Result<()>
+    where
+        F: Fn(&MediaSection) -> bool,
+    {
+        if !self.renegotiate_added {
+            return Ok(());
+        }
+        match self.handle.local_description() {
+            Some(sess_desc) if !sess_desc.media_sections().iter().any(negotiated) => {
+                self.renegotiate()
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Sets the receive quota shared by all the data channels of this connection, `None`
+    /// removes it.
+    ///
+    /// Violations are reported through [`DataChannelHandler::on_quota_exceeded`] of the
+    /// channel that received the offending message, and [`QuotaAction::Close`] closes
+    /// the whole connection.
+    ///
+    /// [`QuotaAction::Close`]: crate::QuotaAction::Close
+    pub fn set_receive_quota(&mut self, quota: Option<Quota>) {
+        *self.context.quota.lock() = quota.map(QuotaTracker::new);
     }
 
+    /// Sets a callback checking the DTLS fingerprint of every remote description.
+    ///
+    /// Remote descriptions without fingerprint or for which `verify` returns false are
+    /// rejected by [`set_remote_description`] with [`Error::FingerprintMismatch`], which
+    /// prevents connecting to a peer impersonated by an untrusted signaling channel.
+    ///
+    /// [`set_remote_description`]: RtcPeerConnection::set_remote_description
+    pub fn verify_remote_fingerprint<F>(&mut self, verify: F)
+    where
+        F: Fn(&Fingerprint) -> bool + Send + Sync + 'static,
+    {
+        *self.handle.inner.verifier.write() = Some(Box::new(verify));
+    }
+
+    /// Sets the fingerprint requirements of remote descriptions, which are rejected by
+    /// [`set_remote_description`] with [`Error::PolicyViolation`] when not met.
+    ///
+    /// [`set_remote_description`]: RtcPeerConnection::set_remote_description
+    pub fn set_fingerprint_policy(&mut self, policy: Option<FingerprintPolicy>) {
+        *self.handle.inner.policy.write() = policy;
+    }
+
+    /// Returns a cloneable handle usable from other threads, see [`PeerConnectionHandle`].
+    pub fn handle(&self) -> PeerConnectionHandle {
+        self.handle.clone()
+    }
+
+    /// Sets the local description of the given type, generating it if needed.
+    ///
+    /// Passing [`SdpType::Rollback`] discards a pending local offer and brings the
+    /// signaling state back to stable, which allows resolving offer collisions (glare)
+    /// the same way browsers do.
     pub fn set_local_description(&mut self, sdp_type: SdpType) -> Result<()> {
+        self.handle.set_local_description(sdp_type)
+    }
+
+    pub fn set_remote_description(&mut self, sess_desc: &SessionDescription) -> Result<()> {
+        self.handle.set_remote_description(sess_desc)
+    }
+
+    /// Discards a pending remote offer and brings the signaling state back to stable.
+    ///
+    /// This is the counterpart of calling [`set_local_description`] with
+    /// [`SdpType::Rollback`], for a rollback that has no meaningful SDP attached.
+    ///
+    /// [`set_local_description`]: RtcPeerConnection::set_local_description
+    pub fn rollback_remote_description(&mut self) -> Result<()> {
+        self.handle.rollback_remote_description()
+    }
+
+    pub fn add_remote_candidate(&mut self, cand: &IceCandidate) -> Result<()> {
+        self.handle.add_remote_candidate(cand)
+    }
+
+    pub fn local_description(&self) -> Option<SessionDescription> {
+        self.handle.local_description()
+    }
+
+    /// Waits for the gathering of candidates to complete, then returns the local
+    /// description with all of them embedded, for signaling servers that only accept a
+    /// single offer or answer.
+    ///
+    /// The local description must have been set, explicitly or through auto negotiation.
+    /// This blocks the calling thread, so it must not be called from a handler.
+    pub fn local_description_when_complete(&self, timeout: Duration) -> Result<SessionDescription> {
+        if !self.context.wait_gathering_complete(timeout) {
+            return Err(Error::Timeout);
+        }
+        self.local_description().ok_or(Error::NotAvailable)
+    }
+
+    /// Async version of [`local_description_when_complete`], without timeout.
+    ///
+    /// [`local_description_when_complete`]: RtcPeerConnection::local_description_when_complete
+    #[cfg(feature = "async")]
+    pub fn local_description_when_complete_async(
+        &self,
+    ) -> impl Future<Output = Result<SessionDescription>> + Send + 'static {
+        let complete = self.context.gathering_complete();
+        let handle = self.handle.clone();
+        async move {
+            if let Some(complete) = complete {
+                complete.recv().await.ok();
+            }
+            handle.local_description().ok_or(Error::NotAvailable)
+        }
+    }
+
+    pub fn remote_description(&self) -> Option<SessionDescription> {
+        self.handle.remote_description()
+    }
+
+    pub fn local_address(&self) -> Option<String> {
+        self.handle.local_address()
+    }
+
+    pub fn remote_address(&self) -> Option<String> {
+        self.handle.remote_address()
+    }
+
+    /// The DTLS certificate fingerprint of the remote peer, once its description is set.
+    pub fn remote_fingerprint(&self) -> Option<Fingerprint> {
+        self.handle.remote_fingerprint()
+    }
+
+    pub fn selected_candidate_pair(&self) -> Option<CandidatePair> {
+        self.handle.selected_candidate_pair()
+    }
+
+    /// Why the connection failed, if it did, see [`PeerConnectionHandler::on_failure`].
+    pub fn failure_reason(&self) -> Option<FailureReason> {
+        *self.context.failure_reason.lock()
+    }
+
+    /// Wall-clock time spent in the callbacks of this connection and its data channels,
+    /// handlers and processing of this crate included.
+    ///
+    /// This isn't CPU time: a handler blocking or waiting for a lock counts as well, and
+    /// the work done by libdatachannel outside of callbacks (ICE, DTLS, SCTP) doesn't. It
+    /// still helps finding the connections keeping callback threads the busiest.
+    ///
+    /// libdatachannel has no statistics API to report it through, it is also listed by
+    /// the [`Registry`](crate::Registry) when enabled.
+    pub fn callback_time(&self) -> Duration {
+        Duration::from_nanos(self.context.callback_time.load(Ordering::Relaxed))
+    }
+
+    /// Timer task closing the connection, see [`ConnectionContext::close_deferred`].
+    unsafe fn close_task(context: &Arc<ConnectionContext>, handler: usize) {
+        Self::close_handler(context, handler as *mut P)
+    }
+
+    /// Deletes the connection and notifies `handler` of the closed state unless it
+    /// already has been, `handler` is only used while holding `handler_lock`.
+    unsafe fn close_handler(context: &ConnectionContext, handler: *mut P) {
+        let _span = context.span.entered();
+        event!(INFO, "Closing peer connection");
+
+        context.delete();
+        registry::update_connection(context.id, |info| info.state = ConnectionState::Closed);
+
+        let _guard = context.handler_lock.lock();
+        if context.notify_closed() {
+            (*handler).on_connection_state_change(ConnectionState::Closed);
+        }
+    }
+
+    /// Runs `f` on the handler while holding the lock used by callbacks.
+    pub(crate) fn with_handler<R>(&mut self, f: impl FnOnce(&mut P) -> R) -> R {
+        let _guard = self.context.handler_lock.lock();
+        f(&mut self.pc_handler)
+    }
+
+    /// Checks whether the selected candidate pair changed, in which case
+    /// [`PeerConnectionHandler::on_candidate_pair_change`] is called.
+    ///
+    /// libdatachannel doesn't signal candidate pair switches, the check is done
+    /// automatically when the connection gets connected, periodically with
+    /// [`RtcConfig::candidate_pair_interval`], and can be triggered with this method.
+    pub fn check_candidate_pair(&mut self) {
+        let _guard = self.context.handler_lock.lock();
+        Self::update_candidate_pair(&self.context, &mut self.pc_handler);
+    }
+
+    /// Notifies `pc_handler` if the selected candidate pair changed, must be called
+    /// while holding `handler_lock`.
+    fn update_candidate_pair(context: &ConnectionContext, pc_handler: &mut P) {
+        let pair = match selected_candidate_pair(context.id) {
+            Some(pair) => pair,
+            None => return,
+        };
+        let old = {
+            let mut current = context.candidate_pair.lock();
+            if current.as_ref() == Some(&pair) {
+                return;
+            }
+            current.replace(pair.clone())
+        };
+        event!(INFO, local = %pair.local, remote = %pair.remote, "Candidate pair changed");
+        pc_handler.on_candidate_pair_change(old, pair);
+    }
+}
+
+/// A cloneable, `Send + Sync` handle to a [`RtcPeerConnection`].
+///
+/// It exposes the signaling methods of the connection so that they can be called from
+/// any thread, and doesn't keep the connection alive: once the [`RtcPeerConnection`] is
+/// dropped, its methods return [`Error::InvalidArg`].
+#[derive(Clone)]
+pub struct PeerConnectionHandle {
+    inner: Arc<HandleInner>,
+}
+
+struct HandleInner {
+    id: i32,
+    verifier: RwLock<Option<FingerprintVerifier>>,
+    policy: RwLock<Option<FingerprintPolicy>>,
+    candidate_filter: Option<CandidateFilter>,
+    address_family: AddressFamily,
+}
+
+impl HandleInner {
+    /// Whether a local candidate is signaled, per the address family and the filters.
+    fn keep_candidate(&self, cand: &IceCandidate) -> bool {
+        let filtered = match &self.candidate_filter {
+            Some(filter) => filter(cand),
+            None => true,
+        };
+        filtered && self.address_family.accepts(cand)
+    }
+
+    fn filter_sdp(&self, sdp: String) -> String {
+        match (&self.candidate_filter, self.address_family) {
+            (None, AddressFamily::DualStack) => sdp,
+            _ => filter_candidates(&sdp, &|cand| self.keep_candidate(cand)),
+        }
+    }
+
+    /// Removes the remote candidates of `sdp` excluded by the address family.
+    fn filter_remote_sdp(&self, sdp: String) -> String {
+        match self.address_family {
+            AddressFamily::DualStack => sdp,
+            family => filter_candidates(&sdp, &|cand| family.accepts(cand)),
+        }
+    }
+}
+
+impl PeerConnectionHandle {
+    pub fn set_local_description(&self, sdp_type: SdpType) -> Result<()> {
         let sdp_type = CString::new(sdp_type.val())?;
-        check(unsafe { sys::rtcSetLocalDescription(self.id, sdp_type.as_ptr()) })?;
+        check(unsafe { sys::rtcSetLocalDescription(self.inner.id, sdp_type.as_ptr()) })?;
         Ok(())
     }
 
-    pub fn set_remote_description(&mut self, sess_desc: &SessionDescription) -> Result<()> {
-        let sdp = CString::new(sess_desc.sdp.to_string())?;
+    pub fn set_remote_description(&self, sess_desc: &SessionDescription) -> Result<()> {
+        if let Some(verify) = &*self.inner.verifier.read() {
+            match sess_desc.fingerprint() {
+                Some(fingerprint) if verify(&fingerprint) => (),
+                _ => return Err(Error::FingerprintMismatch),
+            }
+        }
+        if let Some(policy) = &*self.inner.policy.read() {
+            policy.check(sess_desc)?;
+        }
+
+        let sdp = CString::new(self.inner.filter_remote_sdp(sess_desc.sdp.to_string()))?;
         let sdp_type = CString::new(sess_desc.sdp_type.val())?;
-        check(unsafe { sys::rtcSetRemoteDescription(self.id, sdp.as_ptr(), sdp_type.as_ptr()) })?;
+        check(unsafe {
+            sys::rtcSetRemoteDescription(self.inner.id, sdp.as_ptr(), sdp_type.as_ptr())
+        })?;
         Ok(())
     }
 
-    pub fn add_remote_candidate(&mut self, cand: &IceCandidate) -> Result<()> {
+    pub fn rollback_remote_description(&self) -> Result<()> {
+        let sdp = CString::new("")?;
+        let sdp_type = CString::new(SdpType::Rollback.val())?;
+        check(unsafe {
+            sys::rtcSetRemoteDescription(self.inner.id, sdp.as_ptr(), sdp_type.as_ptr())
+        })?;
+        Ok(())
+    }
+
+    /// Adds a remote candidate, candidates excluded by [`RtcConfig::address_family`] are
+    /// ignored.
+    pub fn add_remote_candidate(&self, cand: &IceCandidate) -> Result<()> {
+        if !self.inner.address_family.accepts(cand) {
+            return Ok(());
+        }
         let mid = CString::new(cand.mid.clone())?;
         let cand = CString::new(cand.candidate.clone())?;
-        unsafe { sys::rtcAddRemoteCandidate(self.id, cand.as_ptr(), mid.as_ptr()) };
+        check(unsafe { sys::rtcAddRemoteCandidate(self.inner.id, cand.as_ptr(), mid.as_ptr()) })?;
         Ok(())
     }
 
     pub fn local_description(&self) -> Option<SessionDescription> {
         let sdp = self
             .read_string_ffi(sys::rtcGetLocalDescription, "local_description")
+            .map(|sdp| self.inner.filter_sdp(sdp))
             .map(|sdp| webrtc_sdp::parse_sdp(&sdp, false).map_err(|e| e.to_string()));
 
         let sdp_type = self
@@ -427,61 +1469,13 @@ where
         self.read_string_ffi(sys::rtcGetRemoteAddress, "remote_address")
     }
 
-    pub fn selected_candidate_pair(&self) -> Option<CandidatePair> {
-        let buf_size = check(unsafe {
-            sys::rtcGetSelectedCandidatePair(
-                self.id,
-                ptr::null_mut() as *mut c_char,
-                0,
-                ptr::null_mut() as *mut c_char,
-                0,
-            )
-        });
-
-        let buf_size = match buf_size {
-            Ok(buf_size) => buf_size as usize,
-            Err(err) => {
-                log::error!("Couldn't get buffer size: {}", err);
-                return None;
-            }
-        };
+    pub fn remote_fingerprint(&self) -> Option<Fingerprint> {
+        self.remote_description()
+            .and_then(|sess_desc| sess_desc.fingerprint())
+    }
 
-        let mut local_buf = vec![0; buf_size];
-        let mut remote_buf = vec![0; buf_size];
-        match check(unsafe {
-            sys::rtcGetSelectedCandidatePair(
-                self.id,
-                local_buf.as_mut_ptr() as *mut c_char,
-                buf_size as i32,
-                remote_buf.as_mut_ptr() as *mut c_char,
-                buf_size as i32,
-            )
-        }) {
-            Ok(_) => {
-                let local = crate::ffi_string(&local_buf);
-                let remote = crate::ffi_string(&remote_buf);
-                match (local, remote) {
-                    (Ok(local), Ok(remote)) => Some(CandidatePair { local, remote }),
-                    (Ok(_), Err(err)) | (Err(err), Ok(_)) | (Err(err), Err(_)) => {
-                        log::error!(
-                            "Couldn't get RtcPeerConnection {:p} candidate_pair: {}",
-                            self,
-                            err
-                        );
-                        None
-                    }
-                }
-            }
-            Err(Error::NotAvailable) => None,
-            Err(err) => {
-                log::warn!(
-                    "Couldn't get RtcPeerConnection {:p} candidate_pair: {}",
-                    self,
-                    err
-                );
-                None
-            }
-        }
+    pub fn selected_candidate_pair(&self) -> Option<CandidatePair> {
+        selected_candidate_pair(self.inner.id)
     }
 
     fn read_string_ffi(
@@ -489,52 +1483,99 @@ where
         str_fn: unsafe extern "C" fn(i32, *mut c_char, i32) -> i32,
         prop: &str,
     ) -> Option<String> {
-        let buf_size = match check(unsafe { str_fn(self.id, ptr::null_mut() as *mut c_char, 0) }) {
-            Ok(buf_size) => buf_size as usize,
-            Err(err) => {
-                log::error!("Couldn't get buffer size: {}", err);
-                return None;
-            }
-        };
+        read_string_ffi(self.inner.id, str_fn, prop)
+    }
+}
 
-        let mut buf = vec![0; buf_size];
-        match check(unsafe { str_fn(self.id, buf.as_mut_ptr() as *mut c_char, buf_size as i32) }) {
-            Ok(_) => match String::from_utf8(buf) {
-                Ok(local) => Some(local.trim_matches(char::from(0)).to_string()),
-                Err(err) => {
+fn selected_candidate_pair(id: i32) -> Option<CandidatePair> {
+    let buf_size = check(unsafe {
+        sys::rtcGetSelectedCandidatePair(
+            id,
+            ptr::null_mut() as *mut c_char,
+            0,
+            ptr::null_mut() as *mut c_char,
+            0,
+        )
+    });
+
+    let buf_size = match buf_size {
+        Ok(buf_size) => buf_size as usize,
+        Err(err) => {
+            log::error!("Couldn't get buffer size: {}", err);
+            return None;
+        }
+    };
+
+    let mut local_buf = vec![0; buf_size];
+    let mut remote_buf = vec![0; buf_size];
+    match check(unsafe {
+        sys::rtcGetSelectedCandidatePair(
+            id,
+            local_buf.as_mut_ptr() as *mut c_char,
+            buf_size as i32,
+            remote_buf.as_mut_ptr() as *mut c_char,
+            buf_size as i32,
+        )
+    }) {
+        Ok(_) => {
+            let local = crate::ffi_string(&local_buf);
+            let remote = crate::ffi_string(&remote_buf);
+            match (local, remote) {
+                (Ok(local), Ok(remote)) => Some(CandidatePair { local, remote }),
+                (Ok(_), Err(err)) | (Err(err), Ok(_)) | (Err(err), Err(_)) => {
                     log::error!(
-                        "Couldn't get RtcPeerConnection {:p} {}: {}",
-                        self,
-                        prop,
+                        "Couldn't get RtcPeerConnection id={} candidate_pair: {}",
+                        id,
                         err
                     );
                     None
                 }
-            },
-            Err(Error::NotAvailable) => None,
-            Err(err) => {
-                log::warn!(
-                    "Couldn't get RtcPeerConnection {:p} {}: {}",
-                    self,
-                    prop,
-                    err
-                );
-                None
             }
         }
+        Err(Error::NotAvailable) => None,
+        Err(err) => {
+            log::warn!(
+                "Couldn't get RtcPeerConnection id={} candidate_pair: {}",
+                id,
+                err
+            );
+            None
+        }
+    }
+}
+
+fn read_string_ffi(
+    id: i32,
+    str_fn: unsafe extern "C" fn(i32, *mut c_char, i32) -> i32,
+    prop: &str,
+) -> Option<String> {
+    let buf_size = match check(unsafe { str_fn(id, ptr::null_mut() as *mut c_char, 0) }) {
+        Ok(buf_size) => buf_size as usize,
+        Err(err) => {
+            log::error!("Couldn't get buffer size: {}", err);
+            return None;
+        }
+    };
+
+    let mut buf = vec![0; buf_size];
+    match check(unsafe { str_fn(id, buf.as_mut_ptr() as *mut c_char, buf_size as i32) }) {
+        Ok(_) => {
+            let value = crate::lossy_string(&buf);
+            Some(value.trim_matches(char::from(0)).to_string())
+        }
+        Err(Error::NotAvailable) => None,
+        Err(err) => {
+            log::warn!("Couldn't get RtcPeerConnection id={} {}: {}", id, prop, err);
+            None
+        }
     }
 }
 
 impl<P> Drop for RtcPeerConnection<P> {
     fn drop(&mut self) {
-        match check(unsafe { sys::rtcDeletePeerConnection(self.id) }) {
-            Err(err) => log::error!(
-                "Error while dropping RtcPeerConnection id={} {:p}: {}",
-                self.id,
-                self,
-                err
-            ),
-            _ => (),
-        }
+        self.context.owner.lock().set(0);
+        registry::remove_connection(self.id);
+        self.context.delete();
+        crate::object_dropped();
     }
 }
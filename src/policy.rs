@@ -0,0 +1,99 @@
+use webrtc_sdp::attribute_type::SdpAttribute;
+
+use crate::error::{Error, Result};
+use crate::peerconnection::SessionDescription;
+
+/// Requirements on the certificate fingerprints and media protocols advertised by
+/// remote descriptions, see [`RtcPeerConnection::set_fingerprint_policy`].
+///
+/// This only checks the SDP: the hash function of the fingerprints, and that media
+/// sections use a DTLS transport. It doesn't enforce a DTLS version or cipher suite,
+/// which libdatachannel neither exposes nor lets configure.
+///
+/// [`RtcPeerConnection::set_fingerprint_policy`]: crate::RtcPeerConnection::set_fingerprint_policy
+#[derive(Debug, Clone, PartialEq)]
+pub struct FingerprintPolicy {
+    /// Accepted fingerprint hash functions, e.g. `sha-256`, compared ignoring case.
+    pub fingerprint_algorithms: Vec<String>,
+    /// Whether media sections must be encrypted with DTLS, which rejects plain RTP as
+    /// well as SDES keying.
+    pub require_dtls: bool,
+}
+
+impl Default for FingerprintPolicy {
+    fn default() -> Self {
+        FingerprintPolicy {
+            fingerprint_algorithms: vec![
+                "sha-256".to_string(),
+                "sha-384".to_string(),
+                "sha-512".to_string(),
+            ],
+            require_dtls: true,
+        }
+    }
+}
+
+impl FingerprintPolicy {
+    pub fn fingerprint_algorithms<S: AsRef<str>>(mut self, algorithms: &[S]) -> Self {
+        self.fingerprint_algorithms = algorithms
+            .iter()
+            .map(|algorithm| algorithm.as_ref().to_lowercase())
+            .collect();
+        self
+    }
+
+    pub fn require_dtls(mut self, require_dtls: bool) -> Self {
+        self.require_dtls = require_dtls;
+        self
+    }
+
+    /// Returns [`Error::PolicyViolation`] if `sess_desc` doesn't comply with the policy.
+    pub fn check(&self, sess_desc: &SessionDescription) -> Result<()> {
+        if self.require_dtls {
+            for media in &sess_desc.sdp.media {
+                let proto = media.get_proto().to_string();
+                if !is_dtls(&proto) {
+                    return Err(Error::PolicyViolation(format!(
+                        "media transport {} isn't DTLS",
+                        proto
+                    )));
+                }
+            }
+        }
+
+        let media = sess_desc
+            .sdp
+            .media
+            .iter()
+            .flat_map(|media| media.get_attributes());
+        let mut fingerprints = 0;
+        for attribute in sess_desc.sdp.attribute.iter().chain(media) {
+            if let SdpAttribute::Fingerprint(fingerprint) = attribute {
+                let algorithm = fingerprint.hash_algorithm.to_string();
+                if !self
+                    .fingerprint_algorithms
+                    .iter()
+                    .any(|allowed| allowed.eq_ignore_ascii_case(&algorithm))
+                {
+                    return Err(Error::PolicyViolation(format!(
+                        "fingerprint algorithm {} isn't allowed",
+                        algorithm
+                    )));
+                }
+                fingerprints += 1;
+            }
+        }
+
+        if fingerprints == 0 {
+            return Err(Error::PolicyViolation("no fingerprint".to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// Whether a media transport protocol is secured with DTLS, e.g. `UDP/DTLS/SCTP` or
+/// `UDP/TLS/RTP/SAVPF` (RFC 5764). SDES keying uses `RTP/SAVP(F)`, which isn't.
+fn is_dtls(proto: &str) -> bool {
+    let proto = proto.to_uppercase();
+    proto.contains("DTLS") || proto.contains("/TLS/RTP/SAVP")
+}
@@ -0,0 +1,218 @@
+use std::convert::TryInto;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use parking_lot::{Condvar, Mutex};
+
+use crate::datachannel::{DataChannelHandle, DataChannelHandler, Message};
+use crate::error::{Error, Result};
+use crate::wrapper::{deliver, deliver_held, forward_handler, wrapper_accessors};
+
+const MAGIC: &[u8; 4] = b"\xffDCP";
+const REQUEST: u8 = 0;
+const REPLY: u8 = 1;
+const HEADER_SIZE: usize = MAGIC.len() + 1 + 4;
+
+/// How long a probe waits for its reply before being counted as lost.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Default)]
+struct Pending {
+    seq: Option<u32>,
+    received_at: Option<Instant>,
+}
+
+#[derive(Default)]
+struct Shared {
+    handle: Mutex<Option<DataChannelHandle>>,
+    pending: Mutex<Pending>,
+    replied: Condvar,
+}
+
+/// A [`DataChannelHandler`] wrapper answering the latency probes of the remote peer, and
+/// measuring its own with [`Prober::probe`].
+///
+/// Both peers must wrap their handlers, other messages are passed through unchanged.
+/// Probes are answered from the message callback, which is serialized with the wrapped
+/// handler: the measured RTT includes the time the remote handler spends on the
+/// messages received before the probe.
+pub struct Probed<D> {
+    inner: D,
+    shared: Arc<Shared>,
+}
+
+impl<D> Probed<D>
+where
+    D: DataChannelHandler,
+{
+    pub fn new(inner: D) -> Self {
+        Probed {
+            inner,
+            shared: Arc::new(Shared::default()),
+        }
+    }
+
+    /// Returns a prober that can be kept to probe once the handler has been moved into a
+    /// data channel.
+    pub fn prober(&self) -> Prober {
+        Prober {
+            shared: self.shared.clone(),
+        }
+    }
+
+    /// Handles `msg` if it is a probe, returning whether it was.
+    fn handle_probe(&mut self, msg: &[u8]) -> bool {
+        if msg.len() < HEADER_SIZE || &msg[..MAGIC.len()] != MAGIC {
+            return false;
+        }
+        let seq = u32::from_be_bytes(msg[MAGIC.len() + 1..HEADER_SIZE].try_into().unwrap());
+        match msg[MAGIC.len()] {
+            REQUEST => {
+                let mut reply = msg.to_vec();
+                reply[MAGIC.len()] = REPLY;
+                if let Some(handle) = &*self.shared.handle.lock() {
+                    if let Err(err) = handle.send(&reply) {
+                        log::warn!("Couldn't answer latency probe: {}", err);
+                    }
+                }
+            }
+            _ => {
+                let mut pending = self.shared.pending.lock();
+                if pending.seq == Some(seq) {
+                    pending.received_at = Some(Instant::now());
+                    self.shared.replied.notify_all();
+                }
+            }
+        }
+        true
+    }
+}
+
+wrapper_accessors!(Probed);
+
+impl<D> DataChannelHandler for Probed<D>
+where
+    D: DataChannelHandler,
+{
+    forward_handler!(
+        on_open,
+        on_closed,
+        on_error,
+        on_message,
+        on_buffered_amount_low,
+        on_available,
+        on_quota_exceeded,
+        owned_messages,
+        subscriptions,
+    );
+
+    fn on_created(&mut self, handle: DataChannelHandle) {
+        *self.shared.handle.lock() = Some(handle.clone());
+        self.inner.on_created(handle)
+    }
+
+    fn on_typed_message(&mut self, msg: Message) {
+        if !self.handle_probe(msg.as_bytes()) {
+            deliver(&mut self.inner, msg)
+        }
+    }
+
+    // Passed through rather than to `on_typed_message`, which would copy it
+    fn on_message_owned(&mut self, msg: Vec<u8>) {
+        if !self.handle_probe(&msg) {
+            deliver_held(&mut self.inner, msg, false)
+        }
+    }
+}
+
+/// Measures the RTT of an open data channel whose handler is [`Probed`].
+#[derive(Clone)]
+pub struct Prober {
+    shared: Arc<Shared>,
+}
+
+impl Prober {
+    /// Sends `count` probes of `payload_size` bytes one after the other, and summarizes
+    /// their RTT. Probes without a reply within 5 seconds are counted as lost.
+    ///
+    /// This blocks the calling thread, so it must not be called from a handler.
+    pub fn probe(&self, payload_size: usize, count: u32) -> Result<ProbeSummary> {
+        let handle = self
+            .shared
+            .handle
+            .lock()
+            .clone()
+            .ok_or(Error::NotAvailable)?;
+        let mut msg = vec![0; payload_size.max(HEADER_SIZE)];
+        msg[..MAGIC.len()].copy_from_slice(MAGIC);
+        msg[MAGIC.len()] = REQUEST;
+
+        let mut rtts = Vec::with_capacity(count as usize);
+        for seq in 0..count {
+            msg[MAGIC.len() + 1..HEADER_SIZE].copy_from_slice(&seq.to_be_bytes());
+            *self.shared.pending.lock() = Pending {
+                seq: Some(seq),
+                received_at: None,
+            };
+
+            let sent_at = Instant::now();
+            handle.send(&msg)?;
+
+            let deadline = sent_at + PROBE_TIMEOUT;
+            let mut pending = self.shared.pending.lock();
+            while pending.received_at.is_none() {
+                if self
+                    .shared
+                    .replied
+                    .wait_until(&mut pending, deadline)
+                    .timed_out()
+                {
+                    break;
+                }
+            }
+            if let Some(received_at) = pending.received_at {
+                rtts.push(received_at.duration_since(sent_at));
+            }
+            pending.seq = None;
+        }
+        Ok(ProbeSummary::new(count as usize, rtts))
+    }
+}
+
+/// The RTT distribution measured by [`Prober::probe`], durations are zero when no probe
+/// got a reply.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProbeSummary {
+    pub sent: usize,
+    pub lost: usize,
+    pub min: Duration,
+    pub mean: Duration,
+    pub p50: Duration,
+    pub p90: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+}
+
+impl ProbeSummary {
+    fn new(sent: usize, mut rtts: Vec<Duration>) -> Self {
+        rtts.sort();
+        let percentile = |p: usize| match rtts.len() {
+            0 => Duration::default(),
+            len => rtts[(len * p / 100).min(len - 1)],
+        };
+        let mean = match rtts.len() {
+            0 => Duration::default(),
+            len => rtts.iter().sum::<Duration>() / len as u32,
+        };
+        ProbeSummary {
+            sent,
+            lost: sent - rtts.len(),
+            min: percentile(0),
+            mean,
+            p50: percentile(50),
+            p90: percentile(90),
+            p99: percentile(99),
+            max: rtts.last().copied().unwrap_or_default(),
+        }
+    }
+}
@@ -0,0 +1,204 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+
+use crate::datachannel::{DataChannelHandler, Message, RtcDataChannel};
+use crate::diff::SdpDiff;
+use crate::peerconnection::{
+    CandidatePair, ConnectionState, FailureReason, GatheringState, IceCandidate,
+    PeerConnectionHandler, RtcPeerConnection, SessionDescription, SignalingState,
+};
+use crate::quota::QuotaScope;
+
+/// Identifies the data channel an [`Event`] comes from, see [`QueuedChannel::key`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ChannelKey(pub u64);
+
+/// An event of a connection or of one of its data channels, as queued by [`EventQueue`].
+pub enum Event {
+    Description(SessionDescription),
+    DescriptionDiff(SdpDiff),
+    Candidate(IceCandidate),
+    ConnectionStateChange(ConnectionState),
+    GatheringStateChange(GatheringState),
+    SignalingStateChange(SignalingState),
+    CandidatePairChange {
+        old: Option<CandidatePair>,
+        new: CandidatePair,
+    },
+    Failure(FailureReason),
+    /// A data channel opened by the remote peer, which must be kept to receive its events.
+    DataChannel {
+        channel: ChannelKey,
+        data_channel: Box<RtcDataChannel<QueuedChannel>>,
+    },
+    ChannelOpen(ChannelKey),
+    ChannelClosed(ChannelKey),
+    ChannelError {
+        channel: ChannelKey,
+        err: String,
+    },
+    Message {
+        channel: ChannelKey,
+        msg: Vec<u8>,
+        text: bool,
+    },
+    BufferedAmountLow(ChannelKey),
+    Available(ChannelKey),
+    QuotaExceeded {
+        channel: ChannelKey,
+        scope: QuotaScope,
+    },
+}
+
+#[derive(Clone, Default)]
+struct Shared {
+    events: Arc<Mutex<VecDeque<Event>>>,
+    next_key: Arc<AtomicU64>,
+}
+
+impl Shared {
+    fn push(&self, event: Event) {
+        self.events.lock().push_back(event)
+    }
+}
+
+/// A [`PeerConnectionHandler`] queueing all the events of the connection and of its data
+/// channels, which are then drained from the caller's thread with
+/// [`RtcPeerConnection::poll_event`], e.g. once per frame of a game loop.
+///
+/// Data channels must use the [`QueuedChannel`] handlers returned by
+/// [`RtcPeerConnection::queued_channel`].
+#[derive(Default)]
+pub struct EventQueue {
+    shared: Shared,
+}
+
+impl EventQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn channel(&self) -> QueuedChannel {
+        QueuedChannel {
+            key: ChannelKey(self.shared.next_key.fetch_add(1, Ordering::Relaxed)),
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl PeerConnectionHandler for EventQueue {
+    type DCH = QueuedChannel;
+
+    fn data_channel_handler(&mut self) -> Self::DCH {
+        self.channel()
+    }
+
+    fn on_description(&mut self, sess_desc: SessionDescription) {
+        self.shared.push(Event::Description(sess_desc))
+    }
+
+    fn on_description_diff(&mut self, diff: SdpDiff) {
+        self.shared.push(Event::DescriptionDiff(diff))
+    }
+
+    fn on_candidate(&mut self, cand: IceCandidate) {
+        self.shared.push(Event::Candidate(cand))
+    }
+
+    fn on_connection_state_change(&mut self, state: ConnectionState) {
+        self.shared.push(Event::ConnectionStateChange(state))
+    }
+
+    fn on_gathering_state_change(&mut self, state: GatheringState) {
+        self.shared.push(Event::GatheringStateChange(state))
+    }
+
+    fn on_signaling_state_change(&mut self, state: SignalingState) {
+        self.shared.push(Event::SignalingStateChange(state))
+    }
+
+    fn on_candidate_pair_change(&mut self, old: Option<CandidatePair>, new: CandidatePair) {
+        self.shared.push(Event::CandidatePairChange { old, new })
+    }
+
+    fn on_failure(&mut self, reason: FailureReason) {
+        self.shared.push(Event::Failure(reason))
+    }
+
+    fn on_data_channel(&mut self, data_channel: Box<RtcDataChannel<Self::DCH>>) {
+        let channel = data_channel.handler().key;
+        self.shared.push(Event::DataChannel {
+            channel,
+            data_channel,
+        })
+    }
+}
+
+/// The [`DataChannelHandler`] of the data channels of an [`EventQueue`] connection.
+pub struct QueuedChannel {
+    key: ChannelKey,
+    shared: Shared,
+}
+
+impl QueuedChannel {
+    /// The key of the events of this channel.
+    pub fn key(&self) -> ChannelKey {
+        self.key
+    }
+}
+
+impl DataChannelHandler for QueuedChannel {
+    fn on_open(&mut self) {
+        self.shared.push(Event::ChannelOpen(self.key))
+    }
+
+    fn on_closed(&mut self) {
+        self.shared.push(Event::ChannelClosed(self.key))
+    }
+
+    fn on_error(&mut self, err: &str) {
+        self.shared.push(Event::ChannelError {
+            channel: self.key,
+            err: err.to_string(),
+        })
+    }
+
+    fn on_typed_message(&mut self, msg: Message) {
+        self.shared.push(Event::Message {
+            channel: self.key,
+            msg: msg.as_bytes().to_vec(),
+            text: msg.is_text(),
+        })
+    }
+
+    fn on_buffered_amount_low(&mut self) {
+        self.shared.push(Event::BufferedAmountLow(self.key))
+    }
+
+    fn on_available(&mut self) {
+        self.shared.push(Event::Available(self.key))
+    }
+
+    fn on_quota_exceeded(&mut self, scope: QuotaScope) {
+        self.shared.push(Event::QuotaExceeded {
+            channel: self.key,
+            scope,
+        })
+    }
+}
+
+impl RtcPeerConnection<EventQueue> {
+    /// Pops the oldest queued event, if any, without blocking.
+    pub fn poll_event(&mut self) -> Option<Event> {
+        self.with_handler(|queue| queue.shared.events.lock().pop_front())
+    }
+
+    /// Returns a handler for a new data channel of this connection, to be passed to
+    /// `create_data_channel`. Its events are queued under [`QueuedChannel::key`].
+    pub fn queued_channel(&mut self) -> QueuedChannel {
+        self.with_handler(|queue| queue.channel())
+    }
+}
@@ -0,0 +1,196 @@
+use std::time::{Duration, Instant};
+
+/// What to do with incoming messages once a [`Quota`] is exceeded.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QuotaAction {
+    /// Only notify through `on_quota_exceeded`, messages are still delivered.
+    Notify,
+    /// Notify and drop messages until the current one second window ends.
+    Drop,
+    /// Notify and close the data channel that received the offending message, or the
+    /// whole connection when its own quota is exceeded. The closing is done from the
+    /// timer thread shortly after, messages received meanwhile are dropped.
+    Close,
+}
+
+/// Which quota has been exceeded, as reported by `on_quota_exceeded`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QuotaScope {
+    Channel,
+    Connection,
+}
+
+/// Receive-side limits, enforced over a one second window.
+///
+/// A limit set to `None` is not enforced.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Quota {
+    pub max_messages_per_sec: Option<u32>,
+    pub max_bytes_per_sec: Option<u64>,
+    pub action: QuotaAction,
+}
+
+impl Default for Quota {
+    fn default() -> Self {
+        Quota {
+            max_messages_per_sec: None,
+            max_bytes_per_sec: None,
+            action: QuotaAction::Notify,
+        }
+    }
+}
+
+impl Quota {
+    pub fn max_messages_per_sec(mut self, max_messages_per_sec: u32) -> Self {
+        self.max_messages_per_sec = Some(max_messages_per_sec);
+        self
+    }
+
+    pub fn max_bytes_per_sec(mut self, max_bytes_per_sec: u64) -> Self {
+        self.max_bytes_per_sec = Some(max_bytes_per_sec);
+        self
+    }
+
+    pub fn action(mut self, action: QuotaAction) -> Self {
+        self.action = action;
+        self
+    }
+}
+
+/// The outcome of [`QuotaTracker::record`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum QuotaCheck {
+    Within,
+    /// The quota is exceeded, `notify` is only true for the first offending message of
+    /// the current window.
+    Exceeded {
+        action: QuotaAction,
+        notify: bool,
+    },
+}
+
+/// Tracks received messages against a [`Quota`], as done for data channels and
+/// connections.
+pub(crate) struct QuotaTracker {
+    quota: Quota,
+    window_start: Instant,
+    messages: u32,
+    bytes: u64,
+    notified: bool,
+}
+
+impl QuotaTracker {
+    pub(crate) fn new(quota: Quota) -> Self {
+        QuotaTracker {
+            quota,
+            window_start: Instant::now(),
+            messages: 0,
+            bytes: 0,
+            notified: false,
+        }
+    }
+
+    /// Records a received message of `size` bytes.
+    pub(crate) fn record(&mut self, size: usize) -> QuotaCheck {
+        self.record_at(size, Instant::now())
+    }
+
+    /// Records a received message of `size` bytes, received at `now`.
+    pub(crate) fn record_at(&mut self, size: usize, now: Instant) -> QuotaCheck {
+        if now.duration_since(self.window_start) >= Duration::from_secs(1) {
+            self.window_start = now;
+            self.messages = 0;
+            self.bytes = 0;
+            self.notified = false;
+        }
+
+        self.messages = self.messages.saturating_add(1);
+        self.bytes = self.bytes.saturating_add(size as u64);
+
+        let too_many = matches!(self.quota.max_messages_per_sec, Some(max) if self.messages > max);
+        let too_large = matches!(self.quota.max_bytes_per_sec, Some(max) if self.bytes > max);
+        if !too_many && !too_large {
+            return QuotaCheck::Within;
+        }
+
+        let notify = !self.notified;
+        self.notified = true;
+        QuotaCheck::Exceeded {
+            action: self.quota.action,
+            notify,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quota_counts_messages_per_window() {
+        let mut tracker = QuotaTracker::new(Quota::default().max_messages_per_sec(2));
+        let now = Instant::now();
+
+        assert_eq!(tracker.record_at(10, now), QuotaCheck::Within);
+        assert_eq!(tracker.record_at(10, now), QuotaCheck::Within);
+        assert_eq!(
+            tracker.record_at(10, now),
+            QuotaCheck::Exceeded {
+                action: QuotaAction::Notify,
+                notify: true,
+            }
+        );
+        // Only the first offending message of a window is notified
+        assert_eq!(
+            tracker.record_at(10, now + Duration::from_millis(500)),
+            QuotaCheck::Exceeded {
+                action: QuotaAction::Notify,
+                notify: false,
+            }
+        );
+
+        // A new window starts one second later
+        let next = now + Duration::from_secs(1);
+        assert_eq!(tracker.record_at(10, next), QuotaCheck::Within);
+        assert_eq!(tracker.record_at(10, next), QuotaCheck::Within);
+        assert_eq!(
+            tracker.record_at(10, next),
+            QuotaCheck::Exceeded {
+                action: QuotaAction::Notify,
+                notify: true,
+            }
+        );
+    }
+
+    #[test]
+    fn test_quota_counts_bytes_and_reports_action() {
+        let quota = Quota::default()
+            .max_bytes_per_sec(100)
+            .action(QuotaAction::Close);
+        let mut tracker = QuotaTracker::new(quota);
+        let now = Instant::now();
+
+        assert_eq!(tracker.record_at(60, now), QuotaCheck::Within);
+        assert_eq!(tracker.record_at(40, now), QuotaCheck::Within);
+        assert_eq!(
+            tracker.record_at(1, now),
+            QuotaCheck::Exceeded {
+                action: QuotaAction::Close,
+                notify: true,
+            }
+        );
+        assert_eq!(
+            tracker.record_at(100, now + Duration::from_secs(1)),
+            QuotaCheck::Within
+        );
+    }
+
+    #[test]
+    fn test_quota_without_limits() {
+        let mut tracker = QuotaTracker::new(Quota::default().action(QuotaAction::Drop));
+        let now = Instant::now();
+        for _ in 0..1000 {
+            assert_eq!(tracker.record_at(usize::MAX, now), QuotaCheck::Within);
+        }
+    }
+}
@@ -0,0 +1,255 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Duration;
+
+use lazy_static::lazy_static;
+use parking_lot::{Mutex, RwLock};
+use serde::Serialize;
+
+use crate::peerconnection::{ConnectionState, GatheringState, SignalingState};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+lazy_static! {
+    // Only written when connections and channels come and go, per-message updates
+    // share the read lock and use the atomics of their entry
+    static ref ENTRIES: RwLock<Entries> = RwLock::new(Entries::default());
+}
+
+#[derive(Default)]
+struct Entries {
+    connections: BTreeMap<i32, ConnectionEntry>,
+    channels: BTreeMap<i32, ChannelEntry>,
+}
+
+struct ConnectionEntry {
+    /// Without `callback_time` and `channels`, which are filled in by snapshots.
+    info: Mutex<ConnectionInfo>,
+    /// In nanoseconds.
+    callback_time: AtomicU64,
+}
+
+struct ChannelEntry {
+    pc_id: i32,
+    label: String,
+    open: AtomicBool,
+    messages_sent: AtomicU64,
+    bytes_sent: AtomicU64,
+    messages_received: AtomicU64,
+    bytes_received: AtomicU64,
+}
+
+impl ConnectionEntry {
+    fn info(&self, channels: Vec<ChannelInfo>) -> ConnectionInfo {
+        ConnectionInfo {
+            callback_time: Duration::from_nanos(self.callback_time.load(Ordering::Relaxed)),
+            channels,
+            ..self.info.lock().clone()
+        }
+    }
+}
+
+impl ChannelEntry {
+    fn info(&self, id: i32) -> ChannelInfo {
+        ChannelInfo {
+            id,
+            label: self.label.clone(),
+            open: self.open.load(Ordering::Relaxed),
+            messages_sent: self.messages_sent.load(Ordering::Relaxed),
+            bytes_sent: self.bytes_sent.load(Ordering::Relaxed),
+            messages_received: self.messages_received.load(Ordering::Relaxed),
+            bytes_received: self.bytes_received.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// A live [`RtcPeerConnection`] as listed by the [`Registry`].
+///
+/// [`RtcPeerConnection`]: crate::RtcPeerConnection
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConnectionInfo {
+    pub id: i32,
+    pub state: ConnectionState,
+    pub gathering_state: GatheringState,
+    pub signaling_state: SignalingState,
+    /// See [`RtcPeerConnection::callback_time`].
+    ///
+    /// [`RtcPeerConnection::callback_time`]: crate::RtcPeerConnection::callback_time
+    pub callback_time: Duration,
+    pub channels: Vec<ChannelInfo>,
+}
+
+/// A live [`RtcDataChannel`] as listed by the [`Registry`].
+///
+/// [`RtcDataChannel`]: crate::RtcDataChannel
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChannelInfo {
+    pub id: i32,
+    pub label: String,
+    pub open: bool,
+    pub messages_sent: u64,
+    pub bytes_sent: u64,
+    pub messages_received: u64,
+    pub bytes_received: u64,
+}
+
+/// A process-wide listing of the live connections and data channels, meant for admin
+/// and debugging endpoints.
+///
+/// The registry is disabled by default, it must be enabled before creating the
+/// connections to track. Snapshots implement `Serialize`, so they can be dumped as
+/// JSON with e.g. `serde_json::to_string(&Registry::snapshot())`.
+pub struct Registry;
+
+impl Registry {
+    pub fn enable() {
+        ENABLED.store(true, Ordering::SeqCst);
+    }
+
+    /// Stops tracking and forgets about all the registered connections.
+    pub fn disable() {
+        ENABLED.store(false, Ordering::SeqCst);
+        let mut entries = ENTRIES.write();
+        entries.connections.clear();
+        entries.channels.clear();
+    }
+
+    pub fn is_enabled() -> bool {
+        ENABLED.load(Ordering::Relaxed)
+    }
+
+    /// Returns the live connections, ordered by id.
+    pub fn snapshot() -> Vec<ConnectionInfo> {
+        let entries = ENTRIES.read();
+        let mut channels = HashMap::<i32, Vec<ChannelInfo>>::new();
+        for (id, channel) in &entries.channels {
+            channels
+                .entry(channel.pc_id)
+                .or_default()
+                .push(channel.info(*id));
+        }
+        entries
+            .connections
+            .iter()
+            .map(|(id, conn)| conn.info(channels.remove(id).unwrap_or_default()))
+            .collect()
+    }
+
+    pub fn connection(id: i32) -> Option<ConnectionInfo> {
+        let entries = ENTRIES.read();
+        let conn = entries.connections.get(&id)?;
+        let channels = entries
+            .channels
+            .iter()
+            .filter(|(_, channel)| channel.pc_id == id)
+            .map(|(id, channel)| channel.info(*id))
+            .collect();
+        Some(conn.info(channels))
+    }
+}
+
+pub(crate) fn add_connection(id: i32) {
+    if !Registry::is_enabled() {
+        return;
+    }
+    let info = ConnectionInfo {
+        id,
+        state: ConnectionState::New,
+        gathering_state: GatheringState::New,
+        signaling_state: SignalingState::Stable,
+        callback_time: Duration::default(),
+        channels: Vec::new(),
+    };
+    let entry = ConnectionEntry {
+        info: Mutex::new(info),
+        callback_time: AtomicU64::new(0),
+    };
+    ENTRIES.write().connections.insert(id, entry);
+}
+
+pub(crate) fn update_connection(id: i32, f: impl FnOnce(&mut ConnectionInfo)) {
+    if !Registry::is_enabled() {
+        return;
+    }
+    if let Some(conn) = ENTRIES.read().connections.get(&id) {
+        f(&mut conn.info.lock())
+    }
+}
+
+pub(crate) fn add_callback_time(id: i32, elapsed: Duration) {
+    if !Registry::is_enabled() {
+        return;
+    }
+    if let Some(conn) = ENTRIES.read().connections.get(&id) {
+        conn.callback_time
+            .fetch_add(elapsed.as_nanos() as u64, Ordering::Relaxed);
+    }
+}
+
+pub(crate) fn remove_connection(id: i32) {
+    if !Registry::is_enabled() {
+        return;
+    }
+    let mut entries = ENTRIES.write();
+    entries.connections.remove(&id);
+    entries.channels.retain(|_, channel| channel.pc_id != id);
+}
+
+pub(crate) fn add_channel(pc_id: i32, id: i32, label: impl FnOnce() -> String) {
+    if !Registry::is_enabled() {
+        return;
+    }
+    let entry = ChannelEntry {
+        pc_id,
+        label: label(),
+        open: AtomicBool::new(false),
+        messages_sent: AtomicU64::new(0),
+        bytes_sent: AtomicU64::new(0),
+        messages_received: AtomicU64::new(0),
+        bytes_received: AtomicU64::new(0),
+    };
+    let mut entries = ENTRIES.write();
+    if entries.connections.contains_key(&pc_id) {
+        entries.channels.insert(id, entry);
+    }
+}
+
+pub(crate) fn set_channel_open(id: i32, open: bool) {
+    if !Registry::is_enabled() {
+        return;
+    }
+    if let Some(channel) = ENTRIES.read().channels.get(&id) {
+        channel.open.store(open, Ordering::Relaxed);
+    }
+}
+
+pub(crate) fn record_sent(id: i32, count: usize, size: usize) {
+    if !Registry::is_enabled() {
+        return;
+    }
+    if let Some(channel) = ENTRIES.read().channels.get(&id) {
+        channel
+            .messages_sent
+            .fetch_add(count as u64, Ordering::Relaxed);
+        channel.bytes_sent.fetch_add(size as u64, Ordering::Relaxed);
+    }
+}
+
+pub(crate) fn record_received(id: i32, size: usize) {
+    if !Registry::is_enabled() {
+        return;
+    }
+    if let Some(channel) = ENTRIES.read().channels.get(&id) {
+        channel.messages_received.fetch_add(1, Ordering::Relaxed);
+        channel
+            .bytes_received
+            .fetch_add(size as u64, Ordering::Relaxed);
+    }
+}
+
+pub(crate) fn remove_channel(id: i32) {
+    if !Registry::is_enabled() {
+        return;
+    }
+    ENTRIES.write().channels.remove(&id);
+}
@@ -0,0 +1,191 @@
+use std::convert::TryInto;
+use std::time::{Duration, Instant};
+
+use crate::datachannel::{DataChannelHandler, Message};
+use crate::wrapper::{deliver, deliver_held, forward_handler, skip_header, wrapper_accessors};
+
+const HEADER_SIZE: usize = 4;
+
+/// Bounds of a [`ReorderBuffer`].
+#[derive(Debug, Clone)]
+pub struct ReorderConfig {
+    /// Maximum number of out-of-order messages held back.
+    pub max_depth: usize,
+    /// Maximum time a message is held back waiting for a missing one, checked when
+    /// messages arrive. Once traffic stops, held messages wait for the next message or
+    /// for the channel to close, whatever their delay.
+    pub max_delay: Duration,
+}
+
+impl Default for ReorderConfig {
+    fn default() -> Self {
+        ReorderConfig {
+            max_depth: 64,
+            max_delay: Duration::from_millis(50),
+        }
+    }
+}
+
+impl ReorderConfig {
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    pub fn max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+}
+
+/// Prefixes outgoing messages with the sequence number expected by a remote
+/// [`ReorderBuffer`].
+#[derive(Debug, Default)]
+pub struct Sequencer {
+    next: u32,
+}
+
+impl Sequencer {
+    /// Returns `msg` framed with the next sequence number, ready to be sent.
+    pub fn frame(&mut self, msg: &[u8]) -> Vec<u8> {
+        let mut framed = Vec::with_capacity(HEADER_SIZE + msg.len());
+        framed.extend_from_slice(&self.next.to_be_bytes());
+        framed.extend_from_slice(msg);
+        self.next = self.next.wrapping_add(1);
+        framed
+    }
+}
+
+struct Pending {
+    seq: u32,
+    received: Instant,
+    text: bool,
+    msg: Vec<u8>,
+}
+
+/// A [`DataChannelHandler`] wrapper restoring the order of messages framed by a
+/// [`Sequencer`], typically on an unordered channel.
+///
+/// Out-of-order messages are held back until the missing ones arrive. When more than
+/// `max_depth` messages are pending, or when a message arrives while a pending one has
+/// waited longer than `max_delay`, the gap is given up and delivery resumes from the
+/// next available message. There is no timer: `max_delay` is only checked on arrival.
+/// Messages arriving after their gap was given up are dropped. Pending messages are flushed, in order, when the channel closes.
+pub struct ReorderBuffer<D> {
+    inner: D,
+    config: ReorderConfig,
+    expected: u32,
+    pending: Vec<Pending>,
+}
+
+impl<D> ReorderBuffer<D>
+where
+    D: DataChannelHandler,
+{
+    pub fn new(inner: D, config: ReorderConfig) -> Self {
+        ReorderBuffer {
+            inner,
+            config,
+            expected: 0,
+            pending: Vec::new(),
+        }
+    }
+
+    fn deliver_ready(&mut self) {
+        while let Some(pos) = self.pending.iter().position(|p| p.seq == self.expected) {
+            let pending = self.pending.swap_remove(pos);
+            deliver_held(&mut self.inner, pending.msg, pending.text);
+            self.expected = self.expected.wrapping_add(1);
+        }
+    }
+
+    /// Skips the current gap, resuming from the closest pending message.
+    fn skip_gap(&mut self) {
+        let expected = self.expected;
+        if let Some(next) = self
+            .pending
+            .iter()
+            .map(|p| p.seq)
+            .min_by_key(|seq| seq.wrapping_sub(expected))
+        {
+            log::debug!(
+                "Giving up on messages {} to {}",
+                expected,
+                next.wrapping_sub(1)
+            );
+            self.expected = next;
+            self.deliver_ready();
+        }
+    }
+
+    /// Whether a pending message has waited longer than `max_delay` at `now`.
+    fn any_expired(&self, now: Instant) -> bool {
+        self.pending
+            .iter()
+            .any(|p| now.duration_since(p.received) > self.config.max_delay)
+    }
+}
+
+wrapper_accessors!(ReorderBuffer);
+
+impl<D> DataChannelHandler for ReorderBuffer<D>
+where
+    D: DataChannelHandler,
+{
+    forward_handler!(
+        on_created,
+        on_open,
+        on_error,
+        on_message,
+        on_buffered_amount_low,
+        on_available,
+        on_quota_exceeded,
+        owned_messages,
+        on_message_owned,
+        subscriptions,
+    );
+
+    fn on_closed(&mut self) {
+        while !self.pending.is_empty() {
+            self.skip_gap();
+        }
+        self.inner.on_closed()
+    }
+
+    fn on_typed_message(&mut self, msg: Message) {
+        let bytes = msg.as_bytes();
+        if bytes.len() < HEADER_SIZE {
+            log::warn!(
+                "Dropping message without sequence number ({} bytes)",
+                bytes.len()
+            );
+            return;
+        }
+        let seq = u32::from_be_bytes(bytes[..HEADER_SIZE].try_into().unwrap());
+        let msg = skip_header(msg, HEADER_SIZE);
+
+        let distance = seq.wrapping_sub(self.expected);
+        if distance >= 1 << 31 {
+            log::debug!("Dropping late message {}, expected {}", seq, self.expected);
+            return;
+        }
+
+        let now = Instant::now();
+        if distance == 0 {
+            deliver(&mut self.inner, msg);
+            self.expected = self.expected.wrapping_add(1);
+            self.deliver_ready();
+        } else if !self.pending.iter().any(|p| p.seq == seq) {
+            self.pending.push(Pending {
+                seq,
+                received: now,
+                text: msg.is_text(),
+                msg: msg.as_bytes().to_vec(),
+            });
+        }
+
+        while self.pending.len() > self.config.max_depth || self.any_expired(now) {
+            self.skip_gap();
+        }
+    }
+}
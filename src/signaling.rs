@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+use crate::peerconnection::{IceCandidate, PeerConnectionHandle, SessionDescription};
+
+/// A description or candidate exchanged with a remote peer.
+///
+/// Serialized as the JSON used by browsers: `{"type", "sdp"}` for descriptions (as
+/// `RTCSessionDescription.toJSON()`), and `{"candidate", "sdpMid"}` for candidates (as
+/// `RTCIceCandidate.toJSON()`, whose other fields are ignored).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Signal {
+    Description(SessionDescription),
+    Candidate(IceCandidate),
+}
+
+impl Signal {
+    /// Sets the remote description or adds the remote candidate of the connection.
+    pub fn apply(&self, pc: &PeerConnectionHandle) -> Result<()> {
+        match self {
+            Signal::Description(sess_desc) => pc.set_remote_description(sess_desc),
+            Signal::Candidate(cand) => pc.add_remote_candidate(cand),
+        }
+    }
+}
+
+/// Exchanges signals with remote peers through a signaling server.
+///
+/// The `websocket` Cargo feature provides an implementation, `WebSocketSignaling`.
+pub trait Signaling {
+    type PeerId;
+
+    fn send(&mut self, peer: &Self::PeerId, signal: Signal) -> Result<()>;
+
+    /// Blocks until a signal is received, returning it along with its sender.
+    fn recv(&mut self) -> Result<(Self::PeerId, Signal)>;
+}
@@ -0,0 +1,58 @@
+use std::collections::BTreeMap;
+use std::sync::Once;
+use std::thread;
+use std::time::Instant;
+
+use lazy_static::lazy_static;
+use parking_lot::{Condvar, Mutex, MutexGuard};
+
+type Task = Box<dyn FnOnce() + Send>;
+
+#[derive(Default)]
+struct Tasks {
+    /// Ordered by deadline, then by scheduling order.
+    queue: BTreeMap<(Instant, u64), Task>,
+    next: u64,
+}
+
+static START: Once = Once::new();
+
+lazy_static! {
+    static ref TASKS: Mutex<Tasks> = Mutex::new(Tasks::default());
+    static ref WAKE: Condvar = Condvar::new();
+}
+
+/// Runs `task` at `at` on the timer thread, which is shared by all connections.
+///
+/// Tasks can't be cancelled, they must check whether they still apply when run and
+/// return quickly.
+pub(crate) fn schedule(at: Instant, task: impl FnOnce() + Send + 'static) {
+    START.call_once(|| {
+        thread::Builder::new()
+            .name("datachannel-timer".into())
+            .spawn(run)
+            .expect("Couldn't spawn the timer thread");
+    });
+
+    let mut tasks = TASKS.lock();
+    let seq = tasks.next;
+    tasks.next += 1;
+    tasks.queue.insert((at, seq), Box::new(task));
+    WAKE.notify_one();
+}
+
+fn run() {
+    let mut tasks = TASKS.lock();
+    loop {
+        match tasks.queue.keys().next().copied() {
+            Some(key) if key.0 <= Instant::now() => {
+                let task = tasks.queue.remove(&key).unwrap();
+                MutexGuard::unlocked(&mut tasks, task);
+            }
+            Some((at, _)) => {
+                WAKE.wait_until(&mut tasks, at);
+            }
+            None => WAKE.wait(&mut tasks),
+        }
+    }
+}
@@ -0,0 +1,238 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tokio::sync::Notify;
+
+use crate::datachannel::{
+    DataChannelHandle, DataChannelHandler, DataChannelInit, RtcDataChannel, Subscriptions,
+};
+use crate::error::{Error, Result};
+use crate::peerconnection::{
+    ConnectionState, GatheringState, IceCandidate, PeerConnectionHandler, RtcPeerConnection,
+    SessionDescription, SignalingState,
+};
+
+/// Amount of buffered data above which `send` waits for the buffer to drain.
+const HIGH_WATERMARK: usize = 1024 * 1024;
+
+#[derive(Default)]
+struct State {
+    open: AtomicBool,
+    closed: AtomicBool,
+    opened: Notify,
+    buffered_amount_low: Notify,
+    messages: Mutex<Option<UnboundedReceiver<Vec<u8>>>>,
+}
+
+/// The [`DataChannelHandler`] of a [`TokioDataChannel`].
+pub struct TokioChannelHandler {
+    messages: Option<UnboundedSender<Vec<u8>>>,
+    state: Arc<State>,
+}
+
+impl Default for TokioChannelHandler {
+    fn default() -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let state = State {
+            messages: Mutex::new(Some(rx)),
+            ..State::default()
+        };
+        TokioChannelHandler {
+            messages: Some(tx),
+            state: Arc::new(state),
+        }
+    }
+}
+
+impl DataChannelHandler for TokioChannelHandler {
+    fn on_open(&mut self) {
+        self.state.open.store(true, Ordering::SeqCst);
+        self.state.opened.notify_one();
+    }
+
+    fn on_closed(&mut self) {
+        self.messages = None;
+        self.state.closed.store(true, Ordering::SeqCst);
+        self.state.opened.notify_one();
+        self.state.buffered_amount_low.notify_one();
+    }
+
+    fn on_error(&mut self, err: &str) {
+        log::warn!("TokioDataChannel error: {}", err);
+    }
+
+    fn on_buffered_amount_low(&mut self) {
+        self.state.buffered_amount_low.notify_one();
+    }
+
+    fn owned_messages(&self) -> bool {
+        true
+    }
+
+    fn on_message_owned(&mut self, msg: Vec<u8>) {
+        if let Some(messages) = &self.messages {
+            messages.send(msg).ok();
+        }
+    }
+
+    fn subscriptions(&self) -> Subscriptions {
+        Subscriptions::none()
+            .error()
+            .message()
+            .buffered_amount_low()
+    }
+}
+
+/// A data channel usable from tokio tasks, with `async` send and receive.
+///
+/// Received messages are queued until read with [`recv`], and [`send`] waits for the
+/// channel to be open and for its send buffer to drain.
+///
+/// [`recv`]: TokioDataChannel::recv
+/// [`send`]: TokioDataChannel::send
+pub struct TokioDataChannel {
+    dc: Box<RtcDataChannel<TokioChannelHandler>>,
+    messages: UnboundedReceiver<Vec<u8>>,
+    state: Arc<State>,
+}
+
+impl TokioDataChannel {
+    /// Creates a data channel on `pc`.
+    pub fn create<P>(
+        pc: &mut RtcPeerConnection<P>,
+        label: &str,
+        init: &DataChannelInit,
+    ) -> Result<Self>
+    where
+        P: PeerConnectionHandler + Send,
+        P::DCH: DataChannelHandler + Send,
+    {
+        let dc = pc.create_data_channel_ex(label, TokioChannelHandler::default(), init)?;
+        Ok(Self::wrap(dc))
+    }
+
+    /// Wraps a data channel opened by the remote peer, as received by
+    /// [`PeerConnectionHandler::on_data_channel`], which is already open.
+    pub fn new(dc: Box<RtcDataChannel<TokioChannelHandler>>) -> Self {
+        dc.handler().state.open.store(true, Ordering::SeqCst);
+        Self::wrap(dc)
+    }
+
+    fn wrap(mut dc: Box<RtcDataChannel<TokioChannelHandler>>) -> Self {
+        let state = dc.handler().state.clone();
+        let messages = state
+            .messages
+            .lock()
+            .take()
+            .expect("TokioChannelHandler already wrapped");
+        if let Err(err) = dc.set_buffered_amount_low_threshold(HIGH_WATERMARK / 2) {
+            log::warn!("Couldn't set buffered amount low threshold: {}", err);
+        }
+        TokioDataChannel {
+            dc,
+            messages,
+            state,
+        }
+    }
+
+    /// Receives the next message, or `None` once the channel is closed.
+    pub async fn recv(&mut self) -> Option<Vec<u8>> {
+        self.messages.recv().await
+    }
+
+    /// Sends a message once the channel is open and its send buffer has room for it.
+    ///
+    /// Fails with [`Error::Closed`] if the channel closes meanwhile.
+    pub async fn send(&mut self, msg: &[u8]) -> Result<()> {
+        while !self.state.open.load(Ordering::SeqCst) {
+            if self.state.closed.load(Ordering::SeqCst) {
+                return Err(Error::Closed);
+            }
+            self.state.opened.notified().await;
+        }
+        while self.dc.buffered_amount() > HIGH_WATERMARK {
+            if self.state.closed.load(Ordering::SeqCst) {
+                return Err(Error::Closed);
+            }
+            self.state.buffered_amount_low.notified().await;
+        }
+        if self.state.closed.load(Ordering::SeqCst) {
+            return Err(Error::Closed);
+        }
+        self.dc.send(msg)
+    }
+
+    pub fn handle(&self) -> DataChannelHandle {
+        self.dc.handle()
+    }
+
+    pub fn inner(&self) -> &RtcDataChannel<TokioChannelHandler> {
+        &self.dc
+    }
+
+    pub fn inner_mut(&mut self) -> &mut RtcDataChannel<TokioChannelHandler> {
+        &mut self.dc
+    }
+}
+
+/// A connection event, as received from a [`TokioPeerConnectionHandler`].
+pub enum TokioEvent {
+    Description(SessionDescription),
+    Candidate(IceCandidate),
+    ConnectionStateChange(ConnectionState),
+    GatheringStateChange(GatheringState),
+    SignalingStateChange(SignalingState),
+    DataChannel(TokioDataChannel),
+}
+
+/// A [`PeerConnectionHandler`] forwarding the signaling events and incoming data
+/// channels of a connection to a tokio channel.
+pub struct TokioPeerConnectionHandler {
+    events: UnboundedSender<TokioEvent>,
+}
+
+impl TokioPeerConnectionHandler {
+    /// Returns the handler along with the receiver of its events.
+    pub fn new() -> (Self, UnboundedReceiver<TokioEvent>) {
+        let (events, rx) = mpsc::unbounded_channel();
+        (TokioPeerConnectionHandler { events }, rx)
+    }
+
+    fn send(&self, event: TokioEvent) {
+        self.events.send(event).ok();
+    }
+}
+
+impl PeerConnectionHandler for TokioPeerConnectionHandler {
+    type DCH = TokioChannelHandler;
+
+    fn data_channel_handler(&mut self) -> Self::DCH {
+        TokioChannelHandler::default()
+    }
+
+    fn on_description(&mut self, sess_desc: SessionDescription) {
+        self.send(TokioEvent::Description(sess_desc))
+    }
+
+    fn on_candidate(&mut self, cand: IceCandidate) {
+        self.send(TokioEvent::Candidate(cand))
+    }
+
+    fn on_connection_state_change(&mut self, state: ConnectionState) {
+        self.send(TokioEvent::ConnectionStateChange(state))
+    }
+
+    fn on_gathering_state_change(&mut self, state: GatheringState) {
+        self.send(TokioEvent::GatheringStateChange(state))
+    }
+
+    fn on_signaling_state_change(&mut self, state: SignalingState) {
+        self.send(TokioEvent::SignalingStateChange(state))
+    }
+
+    fn on_data_channel(&mut self, data_channel: Box<RtcDataChannel<Self::DCH>>) {
+        self.send(TokioEvent::DataChannel(TokioDataChannel::new(data_channel)))
+    }
+}
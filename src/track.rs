@@ -0,0 +1,454 @@
+use std::convert::TryFrom;
+use std::ffi::{c_void, CStr, CString};
+use std::os::raw::c_char;
+use std::ptr;
+use std::slice;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use datachannel_sys as sys;
+
+use crate::error::{check, Error, Result};
+
+#[cfg(feature = "media")]
+use datachannel_sys::{
+    rtcAddTrack, rtcChainRtcpNackResponder, rtcChainRtcpSrReporter, rtcDeleteTrack,
+    rtcGetCurrentTrackTimestamp, rtcGetPreviousTrackSenderReportTimestamp, rtcGetTrackDescription,
+    rtcGetTrackStartTimestamp, rtcPacketizationHandlerInit, rtcSetH264PacketizationHandler,
+    rtcSetNeedsToSendRTCPSR, rtcSetOpusPacketizationHandler, rtcSetRtpConfigurationStartTime,
+    rtcSetTrackRTPTimestamp, rtcStartRtcpSenderReporterRecording, rtcStartTime,
+    rtcTransformSecondsToTimestamp, rtcTransformTimestampToSeconds,
+};
+#[cfg(not(feature = "media"))]
+use disabled::{
+    rtcAddTrack, rtcChainRtcpNackResponder, rtcChainRtcpSrReporter, rtcDeleteTrack,
+    rtcGetCurrentTrackTimestamp, rtcGetPreviousTrackSenderReportTimestamp, rtcGetTrackDescription,
+    rtcGetTrackStartTimestamp, rtcPacketizationHandlerInit, rtcSetH264PacketizationHandler,
+    rtcSetNeedsToSendRTCPSR, rtcSetOpusPacketizationHandler, rtcSetRtpConfigurationStartTime,
+    rtcSetTrackRTPTimestamp, rtcStartRtcpSenderReporterRecording, rtcStartTime,
+    rtcTransformSecondsToTimestamp, rtcTransformTimestampToSeconds,
+};
+
+/// Stand-ins for the media functions of libdatachannel, which are missing from builds
+/// without media support. They are never called since [`ensure_media`] fails first.
+#[cfg(not(feature = "media"))]
+#[allow(non_snake_case)]
+mod disabled {
+    use std::os::raw::c_char;
+
+    use datachannel_sys as sys;
+
+    pub unsafe fn rtcAddTrack(_: i32, _: *const c_char) -> i32 {
+        sys::RTC_ERR_FAILURE
+    }
+
+    pub unsafe fn rtcDeleteTrack(_: i32) -> i32 {
+        sys::RTC_ERR_FAILURE
+    }
+
+    pub unsafe fn rtcGetTrackDescription(_: i32, _: *mut c_char, _: i32) -> i32 {
+        sys::RTC_ERR_FAILURE
+    }
+
+    #[allow(non_camel_case_types, dead_code)]
+    pub struct rtcPacketizationHandlerInit {
+        pub ssrc: u32,
+        pub cname: *const c_char,
+        pub payloadType: u8,
+        pub clockRate: u32,
+        pub maxFragmentSize: u16,
+        pub sequenceNumber: u16,
+        pub timestamp: u32,
+    }
+
+    #[allow(non_camel_case_types, dead_code)]
+    pub struct rtcStartTime {
+        pub seconds: f64,
+        pub since1970: bool,
+        pub timestamp: u32,
+    }
+
+    pub unsafe fn rtcSetH264PacketizationHandler(
+        _: i32,
+        _: *const rtcPacketizationHandlerInit,
+    ) -> i32 {
+        sys::RTC_ERR_FAILURE
+    }
+
+    pub unsafe fn rtcSetOpusPacketizationHandler(
+        _: i32,
+        _: *const rtcPacketizationHandlerInit,
+    ) -> i32 {
+        sys::RTC_ERR_FAILURE
+    }
+
+    pub unsafe fn rtcChainRtcpSrReporter(_: i32) -> i32 {
+        sys::RTC_ERR_FAILURE
+    }
+
+    pub unsafe fn rtcChainRtcpNackResponder(_: i32, _: u32) -> i32 {
+        sys::RTC_ERR_FAILURE
+    }
+
+    pub unsafe fn rtcSetRtpConfigurationStartTime(_: i32, _: *const rtcStartTime) -> i32 {
+        sys::RTC_ERR_FAILURE
+    }
+
+    pub unsafe fn rtcStartRtcpSenderReporterRecording(_: i32) -> i32 {
+        sys::RTC_ERR_FAILURE
+    }
+
+    pub unsafe fn rtcTransformSecondsToTimestamp(_: i32, _: f64, _: *mut u32) -> i32 {
+        sys::RTC_ERR_FAILURE
+    }
+
+    pub unsafe fn rtcTransformTimestampToSeconds(_: i32, _: u32, _: *mut f64) -> i32 {
+        sys::RTC_ERR_FAILURE
+    }
+
+    pub unsafe fn rtcGetCurrentTrackTimestamp(_: i32, _: *mut u32) -> i32 {
+        sys::RTC_ERR_FAILURE
+    }
+
+    pub unsafe fn rtcGetTrackStartTimestamp(_: i32, _: *mut u32) -> i32 {
+        sys::RTC_ERR_FAILURE
+    }
+
+    pub unsafe fn rtcSetTrackRTPTimestamp(_: i32, _: u32) -> i32 {
+        sys::RTC_ERR_FAILURE
+    }
+
+    pub unsafe fn rtcGetPreviousTrackSenderReportTimestamp(_: i32, _: *mut u32) -> i32 {
+        sys::RTC_ERR_FAILURE
+    }
+
+    pub unsafe fn rtcSetNeedsToSendRTCPSR(_: i32) -> i32 {
+        sys::RTC_ERR_FAILURE
+    }
+}
+
+/// Whether this build supports media tracks, see the `media` feature.
+pub fn media_supported() -> bool {
+    cfg!(feature = "media")
+}
+
+fn ensure_media() -> Result<()> {
+    if media_supported() {
+        Ok(())
+    } else {
+        Err(Error::FeatureDisabled("media"))
+    }
+}
+
+/// How often [`RtcTrack::send_frame`] asks for an RTCP sender report.
+const SENDER_REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Codecs which libdatachannel can packetize into RTP, see [`RtcTrack::set_packetizer`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Packetizer {
+    H264,
+    Opus,
+}
+
+/// RTP parameters of an outgoing track, used to packetize its frames and to fill its
+/// RTCP sender reports.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RtpConfig {
+    pub ssrc: u32,
+    pub cname: String,
+    pub payload_type: u8,
+    pub clock_rate: u32,
+    pub max_fragment_size: u16,
+    pub sequence_number: u16,
+    pub timestamp: u32,
+}
+
+impl RtpConfig {
+    /// The ssrc, cname and payload type must match the description of the track, the
+    /// clock rate is 90 kHz for video and usually 48 kHz for audio.
+    pub fn new(ssrc: u32, cname: &str, payload_type: u8, clock_rate: u32) -> Self {
+        RtpConfig {
+            ssrc,
+            cname: cname.to_string(),
+            payload_type,
+            clock_rate,
+            max_fragment_size: 1200,
+            sequence_number: 0,
+            timestamp: 0,
+        }
+    }
+
+    /// Maximum size of RTP payloads, 1200 bytes by default.
+    pub fn max_fragment_size(mut self, max_fragment_size: u16) -> Self {
+        self.max_fragment_size = max_fragment_size;
+        self
+    }
+
+    /// Initial RTP sequence number, 0 by default.
+    pub fn sequence_number(mut self, sequence_number: u16) -> Self {
+        self.sequence_number = sequence_number;
+        self
+    }
+
+    /// Initial RTP timestamp, 0 by default.
+    pub fn timestamp(mut self, timestamp: u32) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
+}
+
+#[allow(unused_variables)]
+pub trait TrackHandler {
+    fn on_open(&mut self) {}
+    fn on_closed(&mut self) {}
+    fn on_error(&mut self, err: &str) {}
+    fn on_message(&mut self, msg: &[u8]) {}
+}
+
+/// A media track, created with [`RtcPeerConnection::add_track`].
+///
+/// Without the `media` feature, tracks can't be created and their methods return
+/// [`Error::FeatureDisabled`], so that the same code supports slim and full builds.
+///
+/// [`RtcPeerConnection::add_track`]: crate::RtcPeerConnection::add_track
+pub struct RtcTrack<T> {
+    id: i32,
+    t_handler: T,
+}
+
+impl<T> RtcTrack<T>
+where
+    T: TrackHandler + Send,
+{
+    pub(crate) fn new(pc_id: i32, description: &str, t_handler: T) -> Result<Box<Self>> {
+        ensure_media()?;
+        let description = CString::new(description)?;
+
+        unsafe {
+            let id = check(rtcAddTrack(pc_id, description.as_ptr()))?;
+            let mut rtc_t = Box::new(RtcTrack { id, t_handler });
+            crate::object_created();
+            let ptr = &mut *rtc_t;
+
+            sys::rtcSetUserPointer(id, ptr as *mut _ as *mut c_void);
+
+            check(sys::rtcSetOpenCallback(id, Some(RtcTrack::<T>::open_cb)))?;
+
+            check(sys::rtcSetClosedCallback(
+                id,
+                Some(RtcTrack::<T>::closed_cb),
+            ))?;
+
+            check(sys::rtcSetErrorCallback(id, Some(RtcTrack::<T>::error_cb)))?;
+
+            check(sys::rtcSetMessageCallback(
+                id,
+                Some(RtcTrack::<T>::message_cb),
+            ))?;
+
+            Ok(rtc_t)
+        }
+    }
+
+    unsafe extern "C" fn open_cb(_: i32, ptr: *mut c_void) {
+        let rtc_t = &mut *(ptr as *mut RtcTrack<T>);
+        rtc_t.t_handler.on_open()
+    }
+
+    unsafe extern "C" fn closed_cb(_: i32, ptr: *mut c_void) {
+        let rtc_t = &mut *(ptr as *mut RtcTrack<T>);
+        rtc_t.t_handler.on_closed()
+    }
+
+    unsafe extern "C" fn error_cb(_: i32, err: *const c_char, ptr: *mut c_void) {
+        let rtc_t = &mut *(ptr as *mut RtcTrack<T>);
+        let err = crate::ffi_str(err);
+        rtc_t.t_handler.on_error(&err)
+    }
+
+    unsafe extern "C" fn message_cb(_: i32, msg: *const c_char, size: i32, ptr: *mut c_void) {
+        let rtc_t = &mut *(ptr as *mut RtcTrack<T>);
+        let msg = if size < 0 {
+            CStr::from_ptr(msg).to_bytes()
+        } else {
+            slice::from_raw_parts(msg as *const u8, size as usize)
+        };
+        rtc_t.t_handler.on_message(msg)
+    }
+
+    /// Sends a media packet, e.g. RTP.
+    pub fn send(&mut self, msg: &[u8]) -> Result<()> {
+        ensure_media()?;
+        let size = i32::try_from(msg.len()).map_err(|_| Error::TooLarge(msg.len()))?;
+        check(unsafe { sys::rtcSendMessage(self.id, msg.as_ptr() as *const c_char, size) })?;
+        Ok(())
+    }
+
+    /// Packetizes frames sent on the track into RTP with `config`, so that [`send`] and
+    /// [`send_frame`] take whole frames (e.g. H264 NAL units or Opus packets).
+    ///
+    /// Must be called before [`chain_sender_reporter`] and [`chain_nack_responder`].
+    ///
+    /// [`send`]: RtcTrack::send
+    /// [`send_frame`]: RtcTrack::send_frame
+    /// [`chain_sender_reporter`]: RtcTrack::chain_sender_reporter
+    /// [`chain_nack_responder`]: RtcTrack::chain_nack_responder
+    pub fn set_packetizer(&mut self, packetizer: Packetizer, config: &RtpConfig) -> Result<()> {
+        ensure_media()?;
+        let cname = CString::new(config.cname.as_str())?;
+        let init = rtcPacketizationHandlerInit {
+            ssrc: config.ssrc,
+            cname: cname.as_ptr(),
+            payloadType: config.payload_type,
+            clockRate: config.clock_rate,
+            maxFragmentSize: config.max_fragment_size,
+            sequenceNumber: config.sequence_number,
+            timestamp: config.timestamp,
+        };
+        check(unsafe {
+            match packetizer {
+                Packetizer::H264 => rtcSetH264PacketizationHandler(self.id, &init),
+                Packetizer::Opus => rtcSetOpusPacketizationHandler(self.id, &init),
+            }
+        })?;
+        Ok(())
+    }
+
+    /// Sends RTCP sender reports for the track, which receivers need to synchronize it
+    /// with the other tracks of the connection (e.g. audio and video).
+    pub fn chain_sender_reporter(&mut self) -> Result<()> {
+        ensure_media()?;
+        check(unsafe { rtcChainRtcpSrReporter(self.id) })?;
+        Ok(())
+    }
+
+    /// Retransmits lost packets requested by receivers, keeping up to `max_stored_packets`.
+    pub fn chain_nack_responder(&mut self, max_stored_packets: u32) -> Result<()> {
+        ensure_media()?;
+        check(unsafe { rtcChainRtcpNackResponder(self.id, max_stored_packets) })?;
+        Ok(())
+    }
+
+    /// Sets the wall clock time matching RTP `timestamp`, which sender reports relate RTP
+    /// timestamps to. Tracks meant to be synchronized must share the same start time.
+    pub fn set_start_time(&mut self, start: SystemTime, timestamp: u32) -> Result<()> {
+        ensure_media()?;
+        let seconds = start
+            .duration_since(UNIX_EPOCH)
+            .map_err(|_| Error::InvalidArg)?
+            .as_secs_f64();
+        let start_time = rtcStartTime {
+            seconds,
+            since1970: true,
+            timestamp,
+        };
+        check(unsafe { rtcSetRtpConfigurationStartTime(self.id, &start_time) })?;
+        Ok(())
+    }
+
+    /// Starts sending sender reports, once the start time is set.
+    pub fn start_sender_reports(&mut self) -> Result<()> {
+        ensure_media()?;
+        check(unsafe { rtcStartRtcpSenderReporterRecording(self.id) })?;
+        Ok(())
+    }
+
+    /// Converts a duration to RTP timestamp units with the clock rate of the track.
+    pub fn duration_to_timestamp(&self, duration: Duration) -> Result<u32> {
+        ensure_media()?;
+        let mut timestamp = 0;
+        check(unsafe {
+            rtcTransformSecondsToTimestamp(self.id, duration.as_secs_f64(), &mut timestamp)
+        })?;
+        Ok(timestamp)
+    }
+
+    /// Converts RTP timestamp units to a duration with the clock rate of the track.
+    pub fn timestamp_to_duration(&self, timestamp: u32) -> Result<Duration> {
+        ensure_media()?;
+        let mut seconds = 0.0;
+        check(unsafe { rtcTransformTimestampToSeconds(self.id, timestamp, &mut seconds) })?;
+        Ok(Duration::from_secs_f64(seconds.max(0.0)))
+    }
+
+    /// The RTP timestamp of the next packet.
+    pub fn timestamp(&self) -> Result<u32> {
+        ensure_media()?;
+        let mut timestamp = 0;
+        check(unsafe { rtcGetCurrentTrackTimestamp(self.id, &mut timestamp) })?;
+        Ok(timestamp)
+    }
+
+    /// The RTP timestamp at the start time.
+    pub fn start_timestamp(&self) -> Result<u32> {
+        ensure_media()?;
+        let mut timestamp = 0;
+        check(unsafe { rtcGetTrackStartTimestamp(self.id, &mut timestamp) })?;
+        Ok(timestamp)
+    }
+
+    /// Sets the RTP timestamp of the next packet.
+    pub fn set_timestamp(&mut self, timestamp: u32) -> Result<()> {
+        ensure_media()?;
+        check(unsafe { rtcSetTrackRTPTimestamp(self.id, timestamp) })?;
+        Ok(())
+    }
+
+    /// The RTP timestamp of the last sender report.
+    pub fn last_sender_report_timestamp(&self) -> Result<u32> {
+        ensure_media()?;
+        let mut timestamp = 0;
+        check(unsafe { rtcGetPreviousTrackSenderReportTimestamp(self.id, &mut timestamp) })?;
+        Ok(timestamp)
+    }
+
+    /// Sends a sender report with the next packet.
+    pub fn request_sender_report(&mut self) -> Result<()> {
+        ensure_media()?;
+        check(unsafe { rtcSetNeedsToSendRTCPSR(self.id) })?;
+        Ok(())
+    }
+
+    /// Sends a frame captured `elapsed` after the start time, setting its RTP timestamp
+    /// from the wall clock rather than counting frames, so that it doesn't drift, and
+    /// sending a sender report every second.
+    pub fn send_frame(&mut self, frame: &[u8], elapsed: Duration) -> Result<()> {
+        let timestamp = self
+            .start_timestamp()?
+            .wrapping_add(self.duration_to_timestamp(elapsed)?);
+        self.set_timestamp(timestamp)?;
+
+        let last_report = self.last_sender_report_timestamp()?;
+        let since_report = self.timestamp_to_duration(timestamp.wrapping_sub(last_report))?;
+        if since_report >= SENDER_REPORT_INTERVAL {
+            self.request_sender_report()?;
+        }
+        self.send(frame)
+    }
+
+    /// The SDP media description of the track.
+    pub fn description(&self) -> Result<String> {
+        ensure_media()?;
+        let buf_size =
+            check(unsafe { rtcGetTrackDescription(self.id, ptr::null_mut() as *mut c_char, 0) })?;
+
+        let mut buf = vec![0; buf_size as usize];
+        check(unsafe {
+            rtcGetTrackDescription(self.id, buf.as_mut_ptr() as *mut c_char, buf_size)
+        })?;
+        crate::ffi_string(&buf)
+    }
+}
+
+impl<T> Drop for RtcTrack<T> {
+    fn drop(&mut self) {
+        if let Err(err) = check(unsafe { rtcDeleteTrack(self.id) }) {
+            log::error!(
+                "Error while dropping RtcTrack id={} {:p}: {}",
+                self.id,
+                self,
+                err
+            );
+        }
+        crate::object_dropped();
+    }
+}
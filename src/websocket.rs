@@ -0,0 +1,139 @@
+use std::io::ErrorKind;
+use std::net::TcpStream;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tungstenite::http::Uri;
+use tungstenite::{Message, WebSocket};
+
+use crate::error::{Error, Result};
+use crate::signaling::{Signal, Signaling};
+
+/// How often `recv` stops waiting for incoming messages to write the queued ones.
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+#[derive(Serialize, Deserialize)]
+struct Frame {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    id: String,
+    #[serde(flatten)]
+    signal: Signal,
+}
+
+fn signaling_error(err: impl ToString) -> Error {
+    Error::Signaling(err.to_string())
+}
+
+/// A [`Signaling`] implementation over a WebSocket, exchanging the JSON signals browsers
+/// use (see [`Signal`]) as text messages.
+///
+/// Peers are identified by an `"id"` field added to the JSON objects, for servers relaying
+/// signals between many peers: it holds the recipient of sent signals and the sender of
+/// received ones. It is omitted for the empty id, e.g. when talking to a single browser.
+///
+/// Only plain `ws://` connections are supported.
+pub struct WebSocketSignaling {
+    socket: WebSocket<TcpStream>,
+    queue: Receiver<Frame>,
+    sender: WebSocketSender,
+}
+
+impl WebSocketSignaling {
+    /// Connects to the signaling server at `url`.
+    pub fn connect(url: &str) -> Result<Self> {
+        let uri = url.parse::<Uri>().map_err(signaling_error)?;
+        let host = uri.host().ok_or(Error::InvalidArg)?;
+        let stream =
+            TcpStream::connect((host, uri.port_u16().unwrap_or(80))).map_err(signaling_error)?;
+        let (socket, _) = tungstenite::client(url, stream).map_err(signaling_error)?;
+        Self::new(socket)
+    }
+
+    /// Accepts a WebSocket connection, e.g. from a browser.
+    pub fn accept(stream: TcpStream) -> Result<Self> {
+        let socket = tungstenite::accept(stream).map_err(signaling_error)?;
+        Self::new(socket)
+    }
+
+    fn new(socket: WebSocket<TcpStream>) -> Result<Self> {
+        socket
+            .get_ref()
+            .set_read_timeout(Some(POLL_INTERVAL))
+            .map_err(signaling_error)?;
+        let (tx, queue) = mpsc::channel();
+        Ok(WebSocketSignaling {
+            socket,
+            queue,
+            sender: WebSocketSender { tx },
+        })
+    }
+
+    /// Returns a sender of signals usable from other threads, typically from the
+    /// `on_description` and `on_candidate` callbacks of a connection.
+    ///
+    /// Its signals are written by `recv`, which must be called in a loop.
+    pub fn sender(&self) -> WebSocketSender {
+        self.sender.clone()
+    }
+
+    fn write(&mut self, frame: &Frame) -> Result<()> {
+        let text = serde_json::to_string(frame).map_err(signaling_error)?;
+        self.socket
+            .write_message(Message::Text(text))
+            .map_err(signaling_error)
+    }
+}
+
+impl Signaling for WebSocketSignaling {
+    type PeerId = String;
+
+    fn send(&mut self, peer: &String, signal: Signal) -> Result<()> {
+        self.write(&Frame {
+            id: peer.clone(),
+            signal,
+        })
+    }
+
+    fn recv(&mut self) -> Result<(String, Signal)> {
+        loop {
+            while let Ok(frame) = self.queue.try_recv() {
+                self.write(&frame)?;
+            }
+
+            let text = match self.socket.read_message() {
+                Ok(Message::Text(text)) => text,
+                Ok(Message::Binary(data)) => String::from_utf8(data)?,
+                Ok(_) => continue,
+                Err(tungstenite::Error::Io(err))
+                    if err.kind() == ErrorKind::WouldBlock || err.kind() == ErrorKind::TimedOut =>
+                {
+                    continue
+                }
+                Err(err) => return Err(signaling_error(err)),
+            };
+            match serde_json::from_str::<Frame>(&text) {
+                Ok(frame) => return Ok((frame.id, frame.signal)),
+                Err(err) => log::warn!("Ignoring invalid signaling message: {}", err),
+            }
+        }
+    }
+}
+
+/// Queues signals to be written by a [`WebSocketSignaling`].
+#[derive(Clone)]
+pub struct WebSocketSender {
+    tx: Sender<Frame>,
+}
+
+impl WebSocketSender {
+    pub fn send(&self, peer: &str, signal: Signal) {
+        let frame = Frame {
+            id: peer.to_string(),
+            signal,
+        };
+        if self.tx.send(frame).is_err() {
+            log::warn!("WebSocketSignaling dropped, signal not sent");
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use crate::datachannel::{DataChannelHandler, Message};
+
+/// Implements `inner` and `inner_mut` for a handler wrapper keeping the handler it wraps
+/// in its `inner` field.
+macro_rules! wrapper_accessors {
+    ($wrapper:ident) => {
+        impl<D> $wrapper<D> {
+            pub fn inner(&self) -> &D {
+                &self.inner
+            }
+
+            pub fn inner_mut(&mut self) -> &mut D {
+                &mut self.inner
+            }
+        }
+    };
+}
+
+/// Implements the listed [`DataChannelHandler`] methods of a handler wrapper, within its
+/// `impl DataChannelHandler` block, by forwarding them to its `inner` field.
+///
+/// The wrappers handle messages in `on_typed_message`, so `on_message` and
+/// `on_message_owned` are turned into binary messages passed to it instead, whichever
+/// the channel calls. `subscriptions` adds messages to those of the wrapped handler,
+/// the wrapper needs them even if the wrapped handler reads them with `receive`.
+///
+/// [`DataChannelHandler`]: crate::DataChannelHandler
+macro_rules! forward_handler {
+    ($($method:ident),+ $(,)?) => {
+        $($crate::wrapper::forward_handler!(@$method);)+
+    };
+    (@on_created) => {
+        fn on_created(&mut self, handle: $crate::datachannel::DataChannelHandle) {
+            self.inner.on_created(handle)
+        }
+    };
+    (@on_open) => {
+        fn on_open(&mut self) {
+            self.inner.on_open()
+        }
+    };
+    (@on_closed) => {
+        fn on_closed(&mut self) {
+            self.inner.on_closed()
+        }
+    };
+    (@on_error) => {
+        fn on_error(&mut self, err: &str) {
+            self.inner.on_error(err)
+        }
+    };
+    (@on_message) => {
+        fn on_message(&mut self, msg: &[u8]) {
+            self.on_typed_message($crate::datachannel::Message::Binary(msg))
+        }
+    };
+    (@on_buffered_amount_low) => {
+        fn on_buffered_amount_low(&mut self) {
+            self.inner.on_buffered_amount_low()
+        }
+    };
+    (@on_available) => {
+        fn on_available(&mut self) {
+            self.inner.on_available()
+        }
+    };
+    (@on_quota_exceeded) => {
+        fn on_quota_exceeded(&mut self, scope: $crate::quota::QuotaScope) {
+            self.inner.on_quota_exceeded(scope)
+        }
+    };
+    (@owned_messages) => {
+        fn owned_messages(&self) -> bool {
+            self.inner.owned_messages()
+        }
+    };
+    (@on_message_owned) => {
+        fn on_message_owned(&mut self, msg: Vec<u8>) {
+            self.on_typed_message($crate::datachannel::Message::Binary(&msg))
+        }
+    };
+    (@subscriptions) => {
+        fn subscriptions(&self) -> $crate::datachannel::Subscriptions {
+            self.inner.subscriptions().message()
+        }
+    };
+}
+
+pub(crate) use forward_handler;
+pub(crate) use wrapper_accessors;
+
+/// Returns `msg` without the header of a wrapper, the first `header_size` bytes.
+///
+/// Text stays text unless the header ends within a character, the rest is then binary.
+pub(crate) fn skip_header(msg: Message, header_size: usize) -> Message {
+    match msg {
+        Message::Text(text) => match text.get(header_size..) {
+            Some(rest) => Message::Text(rest),
+            None => Message::Binary(&text.as_bytes()[header_size..]),
+        },
+        Message::Binary(bytes) => Message::Binary(&bytes[header_size..]),
+    }
+}
+
+/// Delivers a message unwrapped by a wrapper to the handler it wraps, owned if that
+/// handler reads owned messages.
+pub(crate) fn deliver<D>(inner: &mut D, msg: Message)
+where
+    D: DataChannelHandler,
+{
+    if inner.owned_messages() {
+        inner.on_message_owned(msg.as_bytes().to_vec())
+    } else {
+        inner.on_typed_message(msg)
+    }
+}
+
+/// Same as [`deliver`] for a message the wrapper holds, which is delivered as text if
+/// `text` is set and it is valid UTF-8.
+pub(crate) fn deliver_held<D>(inner: &mut D, msg: Vec<u8>, text: bool)
+where
+    D: DataChannelHandler,
+{
+    if inner.owned_messages() {
+        inner.on_message_owned(msg)
+    } else if text {
+        match String::from_utf8(msg) {
+            Ok(text) => inner.on_typed_message(Message::Text(&text)),
+            Err(err) => inner.on_typed_message(Message::Binary(err.as_bytes())),
+        }
+    } else {
+        inner.on_typed_message(Message::Binary(&msg))
+    }
+}
@@ -0,0 +1,34 @@
+use datachannel::{AddressFamily, IceCandidate, RtcConfig};
+
+#[test]
+fn test_candidate_fields() {
+    let cand = IceCandidate {
+        candidate: "candidate:1 1 UDP 2122317823 192.168.1.10 54321 typ host".to_string(),
+        mid: "0".to_string(),
+    };
+    assert_eq!(cand.candidate_type(), Some("host"));
+    assert_eq!(cand.address(), Some("192.168.1.10"));
+
+    let cand = IceCandidate {
+        candidate: "candidate:2 1 UDP 16777215 203.0.113.7 3478 typ relay raddr 0.0.0.0 rport 0"
+            .to_string(),
+        mid: "0".to_string(),
+    };
+    assert_eq!(cand.candidate_type(), Some("relay"));
+    assert_eq!(cand.address(), Some("203.0.113.7"));
+    assert_eq!(cand.related_address(), Some("0.0.0.0"));
+
+    let cand = IceCandidate {
+        candidate: "candidate:3 1 UDP 2122317823 4a1f0c1e.local 54321 typ host".to_string(),
+        mid: "0".to_string(),
+    };
+    assert_eq!(cand.address(), Some("4a1f0c1e.local"));
+}
+
+#[test]
+fn test_last_address_family_wins() {
+    let config = RtcConfig::new::<&str>(&[])
+        .address_family(AddressFamily::Ipv4Only)
+        .address_family(AddressFamily::DualStack);
+    assert_eq!(config.address_family, AddressFamily::DualStack);
+}
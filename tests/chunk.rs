@@ -0,0 +1,46 @@
+use std::time::{Duration, Instant};
+
+use datachannel::{ChunkConfig, ChunkSizer};
+
+#[test]
+fn test_chunk_size_adapts_to_drain_rate() {
+    let config = ChunkConfig::default();
+    let mut sizer = ChunkSizer::new(config.clone());
+    assert_eq!(sizer.chunk_size(), config.initial_size);
+
+    // Nothing drained, e.g. a stalled path
+    let start = Instant::now();
+    sizer.record_buffered_at(0, start);
+    sizer.record_sent(1024 * 1024);
+    sizer.record_buffered_at(1024 * 1024, start + Duration::from_millis(10));
+    assert_eq!(sizer.chunk_size(), config.min_size);
+
+    // A fast link drains a lot of data quickly
+    let mut sizer = ChunkSizer::new(config.clone());
+    sizer.record_rtt(Duration::from_millis(50));
+    sizer.record_buffered_at(0, start);
+    sizer.record_sent(1024 * 1024 * 1024);
+    sizer.record_buffered_at(0, start + Duration::from_millis(10));
+    assert_eq!(sizer.chunk_size(), config.max_size);
+
+    let data = vec![0; config.max_size + 1];
+    let (chunk, rest) = sizer.split(&data);
+    assert_eq!((chunk.len(), rest.len()), (config.max_size, 1));
+}
+
+#[test]
+fn test_drain_rate() {
+    let mut sizer = ChunkSizer::default();
+    let start = Instant::now();
+    sizer.record_buffered_at(1000, start);
+    assert_eq!(sizer.drain_rate(), None);
+
+    // 500 sent and 1000 drained out of 1500 in half a second
+    sizer.record_sent(500);
+    sizer.record_buffered_at(500, start + Duration::from_millis(500));
+    assert_eq!(sizer.drain_rate(), Some(2000.0));
+
+    // Samples at the same instant are ignored
+    sizer.record_buffered_at(0, start + Duration::from_millis(500));
+    assert_eq!(sizer.drain_rate(), Some(2000.0));
+}
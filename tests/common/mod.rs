@@ -0,0 +1,30 @@
+#![allow(dead_code)]
+
+use std::sync::{Arc, Mutex};
+
+use datachannel::DataChannelHandler;
+
+/// Records the messages it receives, clones share the record.
+#[derive(Clone, Default)]
+pub struct Collect {
+    received: Arc<Mutex<Vec<Vec<u8>>>>,
+}
+
+impl Collect {
+    pub fn received(&self) -> Vec<Vec<u8>> {
+        self.received.lock().unwrap().clone()
+    }
+
+    pub fn received_text(&self) -> Vec<String> {
+        self.received()
+            .iter()
+            .map(|msg| String::from_utf8_lossy(msg).to_string())
+            .collect()
+    }
+}
+
+impl DataChannelHandler for Collect {
+    fn on_message(&mut self, msg: &[u8]) {
+        self.received.lock().unwrap().push(msg.to_vec());
+    }
+}
@@ -0,0 +1,55 @@
+#![cfg(feature = "correlation")]
+
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use datachannel::{frame_correlated, Correlated, CorrelationId, DataChannelHandler};
+
+#[derive(Clone, Default)]
+struct Replies {
+    sent: Arc<Mutex<Vec<Vec<u8>>>>,
+}
+
+impl DataChannelHandler for Replies {
+    fn on_message(&mut self, msg: &[u8]) {
+        // Answer as if sending back through the channel
+        self.sent.lock().unwrap().push(frame_correlated(msg));
+    }
+}
+
+#[test]
+fn test_correlation_propagates() {
+    let replies = Replies::default();
+    let mut handler = Correlated::new(replies.clone());
+
+    let id = CorrelationId::new();
+    handler.on_message(&id.frame(b"request"));
+    handler.on_message(&frame_correlated(b"untraced"));
+    assert_eq!(CorrelationId::current(), None);
+
+    let mut remote = Correlated::new(Replies::default());
+    let sent = replies.sent.lock().unwrap().clone();
+    assert_eq!(sent[0], id.frame(b"request"));
+    remote.on_message(&sent[1]);
+    assert_eq!(
+        remote.inner().sent.lock().unwrap().clone(),
+        vec![frame_correlated(b"untraced")]
+    );
+}
+
+#[test]
+fn test_correlation_ids_are_unique() {
+    let threads = (0..4)
+        .map(|_| thread::spawn(|| (0..1000).map(|_| CorrelationId::new()).collect::<Vec<_>>()))
+        .collect::<Vec<_>>();
+    let ids = threads
+        .into_iter()
+        .flat_map(|thread| thread.join().unwrap())
+        .collect::<Vec<_>>();
+
+    let unique = ids.iter().collect::<HashSet<_>>();
+    assert_eq!(unique.len(), ids.len());
+    // The random seed is shared by the whole process
+    assert!(ids.iter().all(|id| id.0[..8] == ids[0].0[..8]));
+}
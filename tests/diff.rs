@@ -0,0 +1,96 @@
+use datachannel::sdp::parse_sdp;
+use datachannel::{DirectionChange, MediaDirection, SdpType, SessionDescription};
+
+const HEADER: &str = "v=0\r\n\
+                      o=- 1 1 IN IP4 127.0.0.1\r\n\
+                      s=-\r\n\
+                      t=0 0\r\n";
+
+fn audio(mid: &str, port: u16, direction: &str) -> String {
+    format!(
+        "m=audio {} UDP/TLS/RTP/SAVPF 111\r\n\
+         c=IN IP4 0.0.0.0\r\n\
+         a=mid:{}\r\n\
+         a={}\r\n\
+         a=rtpmap:111 opus/48000/2\r\n",
+        port, mid, direction
+    )
+}
+
+const DATA: &str = "m=application 9 UDP/DTLS/SCTP webrtc-datachannel\r\n\
+                    c=IN IP4 0.0.0.0\r\n\
+                    a=mid:data\r\n\
+                    a=sctp-port:5000\r\n";
+
+fn description(media: &[&str]) -> SessionDescription {
+    let sdp = format!("{}{}", HEADER, media.concat());
+    SessionDescription {
+        sdp: parse_sdp(&sdp, false).unwrap(),
+        sdp_type: SdpType::Offer,
+    }
+}
+
+#[test]
+fn test_media_sections() {
+    let sess_desc = description(&[DATA, &audio("audio", 9, "recvonly")]);
+    let sections = sess_desc.media_sections();
+    assert_eq!(sections.len(), 2);
+    assert_eq!(
+        (sections[0].mid.as_str(), sections[0].kind.as_str()),
+        ("data", "application")
+    );
+    assert_eq!(sections[0].direction, MediaDirection::SendRecv);
+    assert_eq!(
+        (sections[1].mid.as_str(), sections[1].kind.as_str()),
+        ("audio", "audio")
+    );
+    assert_eq!(sections[1].direction, MediaDirection::RecvOnly);
+    assert!(!sections[1].rejected);
+}
+
+#[test]
+fn test_diff_added_section() {
+    let old = description(&[DATA]);
+    let new = description(&[DATA, &audio("audio", 9, "sendrecv")]);
+
+    let diff = new.diff(&old);
+    assert_eq!(diff.added.len(), 1);
+    assert_eq!(diff.added[0].mid, "audio");
+    assert!(diff.removed.is_empty());
+    assert!(diff.direction_changes.is_empty());
+    assert!(new.diff(&new).is_empty());
+}
+
+#[test]
+fn test_diff_removed_section() {
+    let old = description(&[DATA, &audio("audio", 9, "sendrecv")]);
+
+    // Stopped sections keep their m-line with a zero port
+    let stopped = description(&[DATA, &audio("audio", 0, "inactive")]);
+    let diff = stopped.diff(&old);
+    assert_eq!(diff.removed.len(), 1);
+    assert_eq!(diff.removed[0].mid, "audio");
+    assert!(diff.added.is_empty());
+    assert!(diff.direction_changes.is_empty());
+
+    let dropped = description(&[DATA]);
+    assert_eq!(dropped.diff(&old), diff);
+}
+
+#[test]
+fn test_diff_direction_change() {
+    let old = description(&[DATA, &audio("audio", 9, "sendrecv")]);
+    let new = description(&[DATA, &audio("audio", 9, "sendonly")]);
+
+    let diff = new.diff(&old);
+    assert!(diff.added.is_empty());
+    assert!(diff.removed.is_empty());
+    assert_eq!(
+        diff.direction_changes,
+        vec![DirectionChange {
+            mid: "audio".to_string(),
+            old: MediaDirection::SendRecv,
+            new: MediaDirection::SendOnly,
+        }]
+    );
+}
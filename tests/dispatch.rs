@@ -0,0 +1,38 @@
+use std::sync::{Arc, Mutex};
+
+use datachannel::{DataChannelCallbacks, DataChannelHandler, Dispatched, Message, Task};
+
+#[test]
+fn test_dispatched_callbacks_run_on_dispatcher() {
+    let tasks = Arc::new(Mutex::new(Vec::<Task>::new()));
+    let dispatcher = {
+        let tasks = tasks.clone();
+        Arc::new(move |task| tasks.lock().unwrap().push(task))
+    };
+
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let callbacks = {
+        let received = received.clone();
+        DataChannelCallbacks::default().on_typed_message(move |msg| {
+            received
+                .lock()
+                .unwrap()
+                .push((msg.is_text(), msg.as_bytes().to_vec()))
+        })
+    };
+    let mut dc = Dispatched::new(callbacks, dispatcher);
+
+    dc.on_typed_message(Message::Text("hello"));
+    dc.on_typed_message(Message::Binary(&[1, 2, 3]));
+    assert!(received.lock().unwrap().is_empty());
+
+    let pending: Vec<Task> = tasks.lock().unwrap().drain(..).collect();
+    assert_eq!(pending.len(), 2);
+    for task in pending {
+        task();
+    }
+    assert_eq!(
+        *received.lock().unwrap(),
+        vec![(true, b"hello".to_vec()), (false, vec![1, 2, 3])]
+    );
+}
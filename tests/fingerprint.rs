@@ -0,0 +1,48 @@
+use datachannel::sdp::parse_sdp;
+use datachannel::{Fingerprint, SdpType, SessionDescription};
+
+const HEADER: &str = "v=0\r\n\
+                      o=- 1 1 IN IP4 127.0.0.1\r\n\
+                      s=-\r\n\
+                      t=0 0\r\n";
+
+const DATA: &str = "m=application 9 UDP/DTLS/SCTP webrtc-datachannel\r\n\
+                    c=IN IP4 0.0.0.0\r\n\
+                    a=mid:0\r\n\
+                    a=sctp-port:5000\r\n";
+
+const FINGERPRINT: &str = "a=fingerprint:sha-256 00:01:02:03:04:05:06:07:08:09:0a:0b:0c:0d:0e:0f:10:11:12:13:14:15:16:17:18:19:1a:1b:1c:1d:1e:1f\r\n";
+
+fn description(sdp: &str) -> SessionDescription {
+    SessionDescription {
+        sdp: parse_sdp(sdp, false).unwrap(),
+        sdp_type: SdpType::Answer,
+    }
+}
+
+fn expected() -> Fingerprint {
+    Fingerprint {
+        algorithm: "sha-256".to_string(),
+        value: "00:01:02:03:04:05:06:07:08:09:0A:0B:0C:0D:0E:0F:10:11:12:13:14:15:16:17:18:19:1A:1B:1C:1D:1E:1F".to_string(),
+    }
+}
+
+#[test]
+fn test_session_fingerprint() {
+    let sess_desc = description(&format!("{}{}{}", HEADER, FINGERPRINT, DATA));
+    assert_eq!(sess_desc.fingerprint(), Some(expected()));
+}
+
+#[test]
+fn test_media_fingerprint() {
+    let sess_desc = description(&format!("{}{}{}", HEADER, DATA, FINGERPRINT));
+    let fingerprint = sess_desc.fingerprint().unwrap();
+    assert_eq!(fingerprint, expected());
+    assert!(fingerprint.matches("00:01:02:03:04:05:06:07:08:09:0a:0b:0c:0d:0e:0f:10:11:12:13:14:15:16:17:18:19:1a:1b:1c:1d:1e:1f"));
+}
+
+#[test]
+fn test_missing_fingerprint() {
+    let sess_desc = description(&format!("{}{}", HEADER, DATA));
+    assert_eq!(sess_desc.fingerprint(), None);
+}
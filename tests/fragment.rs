@@ -0,0 +1,54 @@
+use datachannel::{fragment, DataChannelHandler, Fragmented, Message};
+
+mod common;
+
+use common::Collect;
+
+#[test]
+fn test_fragment_reassembly() {
+    let collect = Collect::default();
+    let mut handler = Fragmented::new(collect.clone());
+
+    let large = (0..10_000).map(|i| i as u8).collect::<Vec<_>>();
+    let fragments = fragment(&large, 1024).unwrap();
+    assert_eq!(fragments.len(), 10);
+    assert!(fragments.iter().all(|fragment| fragment.len() <= 1024));
+
+    for msg in [&large[..], b"", b"small"].iter() {
+        for fragment in fragment(msg, 1024).unwrap() {
+            handler.on_typed_message(Message::Binary(&fragment));
+        }
+    }
+
+    assert_eq!(collect.received(), vec![large, vec![], b"small".to_vec()]);
+}
+
+#[test]
+fn test_fragment_above_max_size() {
+    let collect = Collect::default();
+    let mut handler = Fragmented::new(collect.clone()).max_size(100);
+
+    for msg in [&[0; 300][..], b"after"].iter() {
+        for fragment in fragment(msg, 64).unwrap() {
+            handler.on_typed_message(Message::Binary(&fragment));
+        }
+    }
+
+    assert_eq!(collect.received(), vec![b"after".to_vec()]);
+}
+
+#[test]
+fn test_fragment_above_length() {
+    let collect = Collect::default();
+    let mut handler = Fragmented::new(collect.clone());
+
+    // A 10 bytes message whose next fragment is too long, the rest of it is skipped
+    handler.on_typed_message(Message::Binary(&10u32.to_be_bytes()));
+    handler.on_typed_message(Message::Binary(&[0; 20]));
+    handler.on_typed_message(Message::Binary(&[0xff; 10]));
+    for fragment in fragment(b"after", 64).unwrap() {
+        handler.on_typed_message(Message::Binary(&fragment));
+    }
+
+    assert_eq!(collect.received(), vec![b"after".to_vec()]);
+}
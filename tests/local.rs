@@ -112,7 +112,7 @@ impl PeerConnectionHandler for LocalConn {
             self.id,
             dc.label(),
             dc.protocol(),
-            dc.reliability()
+            dc.reliability().ok()
         );
         dc.send(format!("PONG from {}", self.id).as_bytes()).ok();
         self.dc.replace(dc);
@@ -0,0 +1,130 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+use datachannel::sdp::parse_sdp;
+use datachannel::{
+    ConnectionManager, DataChannelCallbacks, DataChannelInit, Error, IceCandidate,
+    PeerConnectionCallbacks, RtcConfig, SdpType, SessionDescription, SharedConfig,
+};
+
+const ANSWER: &str = "v=0\r\n\
+                      o=- 1 1 IN IP4 127.0.0.1\r\n\
+                      s=-\r\n\
+                      t=0 0\r\n\
+                      m=application 9 UDP/DTLS/SCTP webrtc-datachannel\r\n\
+                      c=IN IP4 0.0.0.0\r\n\
+                      a=mid:data\r\n\
+                      a=sctp-port:5000\r\n";
+
+fn manager() -> ConnectionManager<&'static str, PeerConnectionCallbacks> {
+    let config = SharedConfig::new(RtcConfig::new::<&str>(&[]));
+    ConnectionManager::new(config, |_| PeerConnectionCallbacks::default())
+}
+
+#[test]
+fn test_connect_and_remove() {
+    let mut manager = manager();
+    manager.connect("alice").unwrap();
+    manager.connect("bob").unwrap();
+    manager.connect("alice").unwrap();
+
+    let mut peers: Vec<_> = manager.peers().copied().collect();
+    peers.sort_unstable();
+    assert_eq!(peers, vec!["alice", "bob"]);
+    assert!(manager.get(&"alice").is_some());
+
+    assert!(manager.remove(&"alice").is_some());
+    assert!(manager.get(&"alice").is_none());
+    assert!(manager.remove(&"alice").is_none());
+    assert_eq!(manager.peers().count(), 1);
+}
+
+#[test]
+fn test_unknown_peer() {
+    let mut manager = manager();
+    let answer = SessionDescription {
+        sdp: parse_sdp(ANSWER, false).unwrap(),
+        sdp_type: SdpType::Answer,
+    };
+    assert!(matches!(
+        manager.set_remote_description("carol", &answer),
+        Err(Error::InvalidArg)
+    ));
+
+    let cand = IceCandidate {
+        candidate: "candidate:1 1 UDP 2122317823 192.168.1.2 50000 typ host".to_string(),
+        mid: "data".to_string(),
+    };
+    assert!(matches!(
+        manager.add_remote_candidate(&"carol", &cand),
+        Err(Error::InvalidArg)
+    ));
+    assert!(matches!(
+        manager.create_data_channel(
+            &"carol",
+            "chat",
+            DataChannelCallbacks::default(),
+            &DataChannelInit::default()
+        ),
+        Err(Error::InvalidArg)
+    ));
+    assert_eq!(manager.peers().count(), 0);
+}
+
+#[test]
+fn test_broadcast() {
+    let mut manager = manager();
+    assert_eq!(manager.broadcast("chat", b"hello"), 0);
+
+    manager.connect("alice").unwrap();
+    let dc = manager
+        .create_data_channel(
+            &"alice",
+            "chat",
+            DataChannelCallbacks::default(),
+            &DataChannelInit::default(),
+        )
+        .unwrap();
+
+    assert_eq!(manager.channel_count(), 1);
+
+    // Not open yet
+    assert_eq!(manager.broadcast("chat", b"hello"), 0);
+    assert_eq!(manager.broadcast("other", b"hello"), 0);
+    assert_eq!(manager.channel_count(), 1);
+
+    // Deleted channels are forgotten
+    drop(dc);
+    assert_eq!(manager.broadcast("chat", b"hello"), 0);
+    assert_eq!(manager.channel_count(), 0);
+}
+
+#[test]
+fn test_failed_peer_is_cleaned_up() {
+    let config = RtcConfig::new::<&str>(&[]).connect_timeout(Duration::from_millis(100));
+    let mut manager = ConnectionManager::new(SharedConfig::new(config), |_| {
+        PeerConnectionCallbacks::default()
+    });
+    manager.connect("alice").unwrap();
+    let _dc = manager
+        .create_data_channel(
+            &"alice",
+            "chat",
+            DataChannelCallbacks::default(),
+            &DataChannelInit::default(),
+        )
+        .unwrap();
+    assert_eq!(manager.channel_count(), 1);
+
+    // The channels go away as soon as the connection fails, without waiting for cleanup
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while manager.channel_count() > 0 {
+        assert!(Instant::now() < deadline, "connection didn't fail");
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(manager.peers().count(), 1);
+
+    assert_eq!(manager.cleanup(), vec!["alice"]);
+    assert_eq!(manager.peers().count(), 0);
+    assert_eq!(manager.channel_count(), 0);
+}
@@ -0,0 +1,28 @@
+use datachannel::{MediaDescription, MediaDirection};
+
+#[test]
+fn test_media_description_sdp() {
+    let media = MediaDescription::video("video")
+        .direction(MediaDirection::SendOnly)
+        .payload_type(96, "H264")
+        .fmtp(96, "profile-level-id=42e01f;packetization-mode=1")
+        .rtcp_fb(96, "nack")
+        .ssrc(42, "stream")
+        .msid("stream", "video");
+    assert_eq!(
+        media.to_string(),
+        "m=video 9 UDP/TLS/RTP/SAVPF 96\r\n\
+         a=mid:video\r\n\
+         a=sendonly\r\n\
+         a=rtcp-mux\r\n\
+         a=rtpmap:96 H264/90000\r\n\
+         a=rtcp-fb:96 nack\r\n\
+         a=fmtp:96 profile-level-id=42e01f;packetization-mode=1\r\n\
+         a=msid:stream video\r\n\
+         a=ssrc:42 cname:stream\r\n\
+         a=ssrc:42 msid:stream video\r\n"
+    );
+
+    let media = MediaDescription::audio("audio").payload_type(111, "opus");
+    assert!(media.to_string().contains("a=rtpmap:111 opus/48000/2\r\n"));
+}
@@ -0,0 +1,81 @@
+#![cfg(feature = "test-util")]
+
+use std::time::Duration;
+
+use datachannel::{
+    connected_pair, DataChannelCall, DataChannelHandler, Message, MockDataChannel,
+    MockPeerConnection, PeerConnectionCall, RtcConfig, SdpType, SignalingState,
+};
+
+#[test]
+fn test_mock_data_channel_records_calls() {
+    let mock = MockDataChannel::default().reply_with(|msg| Some(msg.to_vec()));
+    let calls = mock.calls();
+
+    let mut handler = mock.clone();
+    handler.on_open();
+    handler.on_typed_message(Message::Text("ping"));
+    handler.on_typed_message(Message::Binary(&[1, 2, 3]));
+    handler.on_closed();
+
+    assert!(calls.wait_for(Duration::from_secs(1), |calls| calls.len() == 4));
+    assert_eq!(
+        calls.take(),
+        vec![
+            DataChannelCall::Open,
+            DataChannelCall::Text("ping".to_string()),
+            DataChannelCall::Binary(vec![1, 2, 3]),
+            DataChannelCall::Closed,
+        ]
+    );
+    assert!(mock.calls().calls().is_empty());
+}
+
+#[test]
+fn test_renegotiate_keeps_data_channels() {
+    let offerer = MockPeerConnection::default();
+    let answerer = MockPeerConnection::default()
+        .data_channel_handler(MockDataChannel::default().reply_with(|msg| Some(msg.to_vec())));
+    let offerer_calls = offerer.calls();
+    let answerer_calls = answerer.calls();
+    let config = RtcConfig::new::<&str>(&[]);
+    let (mut pc1, _pc2) = connected_pair(&config, offerer, answerer).unwrap();
+
+    let channel = MockDataChannel::default();
+    let channel_calls = channel.calls();
+    let mut dc = pc1.create_data_channel("test", channel).unwrap();
+    assert!(channel_calls.wait_for(Duration::from_secs(5), |calls| {
+        calls.contains(&DataChannelCall::Open)
+    }));
+
+    let offers = |calls: &[PeerConnectionCall]| {
+        calls
+            .iter()
+            .filter(|call| match call {
+                PeerConnectionCall::Description(desc) => desc.sdp_type == SdpType::Offer,
+                _ => false,
+            })
+            .count()
+    };
+    assert_eq!(offers(&offerer_calls.calls()), 1);
+    answerer_calls.take();
+
+    pc1.renegotiate().unwrap();
+    assert!(offerer_calls.wait_for(Duration::from_secs(5), |calls| offers(calls) == 2));
+    // The new offer is preceded by its changes, which keep the existing sections
+    assert!(offerer_calls.calls().iter().any(|call| match call {
+        PeerConnectionCall::DescriptionDiff(diff) => diff.removed.is_empty(),
+        _ => false,
+    }));
+    assert!(answerer_calls.wait_for(Duration::from_secs(5), |calls| {
+        calls.iter().any(|call| match call {
+            PeerConnectionCall::SignalingStateChange(state) => *state == SignalingState::Stable,
+            _ => false,
+        })
+    }));
+
+    dc.send(b"still open").unwrap();
+    assert!(channel_calls.wait_for(Duration::from_secs(5), |calls| {
+        calls.contains(&DataChannelCall::Binary(b"still open".to_vec()))
+    }));
+}
@@ -0,0 +1,29 @@
+use std::time::Duration;
+
+use crossbeam_channel as chan;
+
+use datachannel::{
+    DataChannelCallbacks, PeerConnectionCallbacks, RtcConfig, RtcPeerConnection, SdpType,
+};
+
+#[test]
+fn test_auto_negotiation_sends_a_single_offer() {
+    let (tx, rx) = chan::unbounded();
+    let callbacks = PeerConnectionCallbacks::default().on_description(move |sess_desc| {
+        tx.send(sess_desc.sdp_type).ok();
+    });
+    let config = RtcConfig::new::<&str>(&[]);
+    assert!(!config.disable_auto_negotiation);
+    let mut pc = RtcPeerConnection::new(&config, callbacks).unwrap();
+
+    let _first = pc
+        .create_data_channel("first", DataChannelCallbacks::default())
+        .unwrap();
+    let _second = pc
+        .create_data_channel("second", DataChannelCallbacks::default())
+        .unwrap();
+
+    let offer = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert_eq!(offer, SdpType::Offer);
+    assert!(rx.recv_timeout(Duration::from_secs(1)).is_err());
+}
@@ -0,0 +1,63 @@
+use datachannel::sdp::parse_sdp;
+use datachannel::{Error, FingerprintPolicy, SdpType, SessionDescription};
+
+const FINGERPRINT_SHA256: &str = "AB:AB:AB:AB:AB:AB:AB:AB:AB:AB:AB:AB:AB:AB:AB:AB:AB:AB:AB:AB:AB:AB:AB:AB:AB:AB:AB:AB:AB:AB:AB:AB";
+const FINGERPRINT_SHA1: &str = "CD:CD:CD:CD:CD:CD:CD:CD:CD:CD:CD:CD:CD:CD:CD:CD:CD:CD:CD:CD";
+
+fn description(fingerprint: &str, media: &str) -> SessionDescription {
+    let sdp = format!(
+        "v=0\r\n\
+         o=- 1 1 IN IP4 127.0.0.1\r\n\
+         s=-\r\n\
+         t=0 0\r\n\
+         a=fingerprint:{}\r\n\
+         {}",
+        fingerprint, media
+    );
+    SessionDescription {
+        sdp: parse_sdp(&sdp, false).unwrap(),
+        sdp_type: SdpType::Offer,
+    }
+}
+
+const DATA: &str = "m=application 9 UDP/DTLS/SCTP webrtc-datachannel\r\n\
+                    c=IN IP4 0.0.0.0\r\n\
+                    a=mid:0\r\n\
+                    a=sctp-port:5000\r\n";
+
+const PLAIN_RTP: &str = "m=audio 9 RTP/AVP 0\r\n\
+                         c=IN IP4 0.0.0.0\r\n\
+                         a=mid:1\r\n\
+                         a=rtpmap:0 PCMU/8000\r\n";
+
+#[test]
+fn test_policy_accepts_dtls_with_allowed_fingerprint() {
+    let policy = FingerprintPolicy::default();
+    let sess_desc = description(&format!("sha-256 {}", FINGERPRINT_SHA256), DATA);
+    assert_eq!(policy.check(&sess_desc), Ok(()));
+}
+
+#[test]
+fn test_policy_rejects_fingerprint_algorithm() {
+    let sess_desc = description(&format!("sha-1 {}", FINGERPRINT_SHA1), DATA);
+    assert!(matches!(
+        FingerprintPolicy::default().check(&sess_desc),
+        Err(Error::PolicyViolation(_))
+    ));
+
+    let policy = FingerprintPolicy::default().fingerprint_algorithms(&["SHA-1"]);
+    assert_eq!(policy.check(&sess_desc), Ok(()));
+}
+
+#[test]
+fn test_policy_rejects_plain_rtp() {
+    let media = format!("{}{}", DATA, PLAIN_RTP);
+    let sess_desc = description(&format!("sha-256 {}", FINGERPRINT_SHA256), &media);
+    assert!(matches!(
+        FingerprintPolicy::default().check(&sess_desc),
+        Err(Error::PolicyViolation(_))
+    ));
+
+    let policy = FingerprintPolicy::default().require_dtls(false);
+    assert_eq!(policy.check(&sess_desc), Ok(()));
+}
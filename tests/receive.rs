@@ -0,0 +1,18 @@
+use datachannel::{
+    DataChannelCallbacks, DataChannelInit, PeerConnectionCallbacks, RtcConfig, RtcPeerConnection,
+};
+
+#[test]
+fn test_receive_on_empty_queue() {
+    let config = RtcConfig::new::<&str>(&[]);
+    let mut pc = RtcPeerConnection::new(&config, PeerConnectionCallbacks::default()).unwrap();
+    let dc_handler = DataChannelCallbacks::default().on_message_owned(|_| ());
+    let mut dc = pc
+        .create_data_channel_ex("owned", dc_handler, &DataChannelInit::default())
+        .unwrap();
+
+    assert_eq!(dc.available_amount(), 0);
+    for _ in 0..3 {
+        assert_eq!(dc.receive().unwrap(), None);
+    }
+}
@@ -0,0 +1,91 @@
+use std::str;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use datachannel::{
+    DataChannelCallbacks, DataChannelHandler, Message, ReorderBuffer, ReorderConfig, Sequencer,
+};
+
+mod common;
+
+use common::Collect;
+
+fn framed(count: usize) -> Vec<Vec<u8>> {
+    let mut seq = Sequencer::default();
+    (0..count)
+        .map(|i| seq.frame(format!("msg{}", i).as_bytes()))
+        .collect()
+}
+
+#[test]
+fn test_reorder() {
+    let collect = Collect::default();
+    let config = ReorderConfig::default().max_delay(Duration::from_secs(60));
+    let mut buffer = ReorderBuffer::new(collect.clone(), config);
+
+    let msgs = framed(4);
+    for i in [1, 3, 0, 2].iter() {
+        buffer.on_message(&msgs[*i]);
+    }
+
+    let received = collect.received_text();
+    assert_eq!(received, vec!["msg0", "msg1", "msg2", "msg3"]);
+}
+
+#[test]
+fn test_reorder_gives_up_on_gaps() {
+    let collect = Collect::default();
+    let config = ReorderConfig::default()
+        .max_depth(2)
+        .max_delay(Duration::from_secs(60));
+    let mut buffer = ReorderBuffer::new(collect.clone(), config);
+
+    let msgs = framed(5);
+    for i in [1, 2, 3, 0, 4].iter() {
+        buffer.on_message(&msgs[*i]);
+    }
+
+    // msg0 is given up once 3 messages are pending, then dropped when it arrives late
+    let received = collect.received_text();
+    assert_eq!(received, vec!["msg1", "msg2", "msg3", "msg4"]);
+}
+
+#[test]
+fn test_reorder_owned_messages() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let sink = received.clone();
+    let callbacks = DataChannelCallbacks::default()
+        .on_message(|_| panic!("Expected owned messages"))
+        .on_message_owned(move |msg| sink.lock().unwrap().push(msg));
+    let mut buffer = ReorderBuffer::new(callbacks, ReorderConfig::default());
+
+    let msgs = framed(2);
+    buffer.on_message(&msgs[1]);
+    buffer.on_message(&msgs[0]);
+
+    let received = received.lock().unwrap().clone();
+    assert_eq!(received, vec![b"msg0".to_vec(), b"msg1".to_vec()]);
+}
+
+#[test]
+fn test_reorder_keeps_text() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let sink = received.clone();
+    let callbacks = DataChannelCallbacks::default().on_typed_message(move |msg| {
+        let text = match msg {
+            Message::Text(text) => text.to_string(),
+            Message::Binary(_) => panic!("Expected text messages"),
+        };
+        sink.lock().unwrap().push(text)
+    });
+    let mut buffer = ReorderBuffer::new(callbacks, ReorderConfig::default());
+
+    // The held message is delivered as text too
+    let msgs = framed(2);
+    for i in [1, 0].iter() {
+        buffer.on_typed_message(Message::Text(str::from_utf8(&msgs[*i]).unwrap()));
+    }
+
+    let received = received.lock().unwrap().clone();
+    assert_eq!(received, vec!["msg0", "msg1"]);
+}
@@ -0,0 +1,34 @@
+use datachannel::{IceCandidate, Signal};
+
+#[test]
+fn test_signal_browser_candidate() {
+    let json = r#"{
+        "candidate": "candidate:1 1 UDP 2122252543 192.168.1.2 50000 typ host",
+        "sdpMid": "0",
+        "sdpMLineIndex": 0,
+        "usernameFragment": "abcd"
+    }"#;
+    match serde_json::from_str::<Signal>(json).unwrap() {
+        Signal::Candidate(cand) => assert_eq!(
+            cand,
+            IceCandidate {
+                candidate: "candidate:1 1 UDP 2122252543 192.168.1.2 50000 typ host".to_string(),
+                mid: "0".to_string(),
+            }
+        ),
+        signal => panic!("Unexpected signal: {:?}", signal),
+    }
+
+    let cand = IceCandidate {
+        candidate: "candidate:1 1 UDP 1 10.0.0.1 9 typ host".to_string(),
+        mid: "data".to_string(),
+    };
+    let json = serde_json::to_value(&Signal::Candidate(cand)).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({
+            "candidate": "candidate:1 1 UDP 1 10.0.0.1 9 typ host",
+            "sdpMid": "data",
+        })
+    );
+}
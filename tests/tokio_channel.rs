@@ -0,0 +1,103 @@
+#![cfg(feature = "tokio")]
+
+use std::time::Duration;
+
+use tokio::sync::mpsc::UnboundedReceiver;
+use tokio::time::timeout;
+
+use datachannel::{
+    DataChannelInit, Error, RtcConfig, RtcPeerConnection, TokioDataChannel, TokioEvent,
+    TokioPeerConnectionHandler,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Relays the signaling of both connections until `answerer` gets a data channel.
+async fn remote_channel(
+    offerer: &RtcPeerConnection<TokioPeerConnectionHandler>,
+    offerer_events: &mut UnboundedReceiver<TokioEvent>,
+    answerer: &RtcPeerConnection<TokioPeerConnectionHandler>,
+    answerer_events: &mut UnboundedReceiver<TokioEvent>,
+) -> TokioDataChannel {
+    let (offerer, answerer) = (offerer.handle(), answerer.handle());
+    loop {
+        tokio::select! {
+            Some(event) = offerer_events.recv() => match event {
+                TokioEvent::Description(sess_desc) => {
+                    answerer.set_remote_description(&sess_desc).unwrap()
+                }
+                TokioEvent::Candidate(cand) => answerer.add_remote_candidate(&cand).unwrap(),
+                _ => (),
+            },
+            Some(event) = answerer_events.recv() => match event {
+                TokioEvent::Description(sess_desc) => {
+                    offerer.set_remote_description(&sess_desc).unwrap()
+                }
+                TokioEvent::Candidate(cand) => offerer.add_remote_candidate(&cand).unwrap(),
+                TokioEvent::DataChannel(dc) => return dc,
+                _ => (),
+            },
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_remote_channel_sends_without_on_open() {
+    let config = RtcConfig::new::<&str>(&[]);
+    let (handler, mut offerer_events) = TokioPeerConnectionHandler::new();
+    let mut offerer = RtcPeerConnection::new(&config, handler).unwrap();
+    let (handler, mut answerer_events) = TokioPeerConnectionHandler::new();
+    let answerer = RtcPeerConnection::new(&config, handler).unwrap();
+
+    let mut local =
+        TokioDataChannel::create(&mut offerer, "tokio", &DataChannelInit::default()).unwrap();
+    let mut remote = timeout(
+        TIMEOUT,
+        remote_channel(
+            &offerer,
+            &mut offerer_events,
+            &answerer,
+            &mut answerer_events,
+        ),
+    )
+    .await
+    .expect("no remote data channel");
+
+    // The remote channel is open when handed over, `on_open` won't come for it
+    timeout(TIMEOUT, remote.send(b"ping"))
+        .await
+        .expect("send waited for the channel to open")
+        .unwrap();
+    let msg = timeout(TIMEOUT, local.recv()).await.unwrap();
+    assert_eq!(msg.as_deref(), Some(&b"ping"[..]));
+}
+
+#[tokio::test]
+async fn test_send_on_remotely_closed_channel() {
+    let config = RtcConfig::new::<&str>(&[]);
+    let (handler, mut offerer_events) = TokioPeerConnectionHandler::new();
+    let mut offerer = RtcPeerConnection::new(&config, handler).unwrap();
+    let (handler, mut answerer_events) = TokioPeerConnectionHandler::new();
+    let answerer = RtcPeerConnection::new(&config, handler).unwrap();
+
+    let local =
+        TokioDataChannel::create(&mut offerer, "tokio", &DataChannelInit::default()).unwrap();
+    let mut remote = timeout(
+        TIMEOUT,
+        remote_channel(
+            &offerer,
+            &mut offerer_events,
+            &answerer,
+            &mut answerer_events,
+        ),
+    )
+    .await
+    .expect("no remote data channel");
+
+    // Deleting the local channel resets its stream, which closes the remote one
+    drop(local);
+    assert_eq!(timeout(TIMEOUT, remote.recv()).await.unwrap(), None);
+
+    let res = timeout(TIMEOUT, remote.send(b"ping")).await.unwrap();
+    assert_eq!(res, Err(Error::Closed));
+}
@@ -218,7 +218,7 @@ impl PeerConnectionHandler for WsConn {
             "Received Datachannel with: label={}, protocol={:?}, reliability={:?}",
             dc.label(),
             dc.protocol(),
-            dc.reliability()
+            dc.reliability().ok()
         );
 
         dc.send(format!("Hello from {}", self.peer_id).as_bytes())